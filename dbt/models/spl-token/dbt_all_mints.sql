@@ -6,6 +6,7 @@
             {'columns': ['block_time']},
             {'columns': ['to_derive_address']},
             {'columns': ['to_owner_address']},
+            {'columns': ['mint']},
         ]
     )
 }}
@@ -16,11 +17,15 @@ select
     i.transaction_hash,
     m.to as to_derive_address,
     ia.owner as to_owner_address,
-    m.amount
+    m.mint,
+    m.amount,
+    m.raw_amount,
+    m.decimals
 from spl.mints m
          inner join spl.instructions i on i.instruction_id = m.instruction_id
          inner join spl.blocks b on b.number = i.block_number
          left join  spl.initialized_accounts ia on ia.account = m."to"
+where i.succeeded
 {% if is_incremental() %}
-where b.number > (select max(block_number) from {{this}})
+and b.number > (select max(block_number) from {{this}})
 {% endif %}
\ No newline at end of file
@@ -2,6 +2,8 @@
 
 select
     DATE_TRUNC('month', p.block_time) as month,
-    sum(p.amount) as total
+    p.mint,
+    sum(p.amount) as total,
+    sum(p.raw_amount::NUMERIC) as raw_total
 from spl.dbt_all_mints p
-group by DATE_TRUNC('month', p.block_time)
\ No newline at end of file
+group by DATE_TRUNC('month', p.block_time), p.mint
\ No newline at end of file
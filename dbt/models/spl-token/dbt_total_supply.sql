@@ -1,3 +1,12 @@
 {{ config(materialized='table') }}
 
-select (SELECT SUM(amount::NUMERIC) FROM spl.mints) - (SELECT COALESCE(SUM(amount::NUMERIC), 0)  FROM spl.burns) as total_supply
\ No newline at end of file
+select
+    m.mint,
+    m.total - COALESCE(b.total, 0) as total_supply,
+    m.raw_total - COALESCE(b.raw_total, 0) as raw_total_supply
+from (select mint, sum(total) as total, sum(raw_total) as raw_total
+      from spl.dbt_mint_per_month
+      group by mint) m
+         left join (select mint, sum(total) as total, sum(raw_total) as raw_total
+                    from spl.dbt_burn_per_month
+                    group by mint) b on b.mint = m.mint
\ No newline at end of file
@@ -0,0 +1,173 @@
+use crate::pb::sf::solana::spl::v1::r#type::{instruction::Item, BalanceChange, BalanceChanges, SplInstructions};
+use crate::{parse_raw_amount, AUTHORITY_TYPE_ACCOUNT_OWNER};
+use std::collections::HashMap;
+use substreams::errors::Error;
+use substreams::scalar::BigInt;
+use substreams::store::{DeltaBigInt, Deltas, StoreAdd, StoreAddBigInt, StoreGet, StoreGetInt64, StoreNew};
+
+pub(crate) const HOLDER_ACCOUNT: &str = "account";
+pub(crate) const HOLDER_OWNER: &str = "owner";
+
+/// Change of a token account's balance in base units implied by a decoded instruction.
+pub(crate) struct BalanceDelta<'a> {
+    pub account: &'a str,
+    pub owner: &'a str,
+    pub mint: &'a str,
+    pub amount: BigInt,
+}
+
+/// Returns the balance changes implied by the transfers, mints, burns, withheld fee withdrawals and
+/// confidential deposits and withdrawals of `map_spl_instructions`. Transfers credit the destination
+/// with their amount net of fees.
+pub(crate) fn balance_deltas(item: &Item) -> Vec<BalanceDelta<'_>> {
+    let delta = |account, owner, mint, amount| BalanceDelta {
+        account,
+        owner,
+        mint,
+        amount,
+    };
+
+    match item {
+        Item::Transfer(transfer) => vec![
+            delta(
+                &transfer.from,
+                &transfer.from_owner,
+                &transfer.mint,
+                parse_raw_amount(&transfer.raw_amount).neg(),
+            ),
+            delta(
+                &transfer.to,
+                &transfer.to_owner,
+                &transfer.mint,
+                parse_raw_amount(&transfer.raw_net_amount),
+            ),
+        ],
+        Item::Mint(mint) => vec![delta(
+            &mint.to,
+            &mint.to_owner,
+            &mint.mint,
+            parse_raw_amount(&mint.raw_amount),
+        )],
+        Item::Burn(burn) => vec![delta(
+            &burn.from,
+            &burn.from_owner,
+            &burn.mint,
+            parse_raw_amount(&burn.raw_amount).neg(),
+        )],
+        Item::WithdrawWithheldTokens(withdraw) => vec![delta(
+            &withdraw.destination,
+            &withdraw.destination_owner,
+            &withdraw.mint,
+            parse_raw_amount(&withdraw.raw_amount),
+        )],
+        Item::ConfidentialDeposit(deposit) => vec![delta(
+            &deposit.account,
+            &deposit.owner,
+            &deposit.mint,
+            parse_raw_amount(&deposit.raw_amount).neg(),
+        )],
+        Item::ConfidentialWithdraw(withdraw) => vec![delta(
+            &withdraw.account,
+            &withdraw.owner,
+            &withdraw.mint,
+            parse_raw_amount(&withdraw.raw_amount),
+        )],
+        _ => vec![],
+    }
+}
+
+/// Accumulates token balances in base units, keyed by token account (`account:<account>:<mint>`)
+/// and by owner (`owner:<owner>:<mint>`), from the balance changes of `map_spl_instructions`
+/// (see `balance_deltas`). When a token account changes owner, its balance at that point is moved
+/// from the old owner to the new one.
+#[substreams::handlers::store]
+fn store_token_balances(spl_instructions: SplInstructions, store: StoreAddBigInt) {
+    // Balance changes of the current transaction's accounts, keyed by `(account, mint)`.
+    let mut transaction_hash = "";
+    let mut transaction_deltas: HashMap<(&str, &str), BigInt> = HashMap::new();
+
+    for (ordinal, instruction) in spl_instructions.instructions.iter().enumerate() {
+        if !instruction.succeeded {
+            continue;
+        }
+
+        if instruction.transaction_hash != transaction_hash {
+            transaction_hash = &instruction.transaction_hash;
+            transaction_deltas.clear();
+        }
+
+        let ordinal = ordinal as u64;
+        let Some(item) = &instruction.item else {
+            continue;
+        };
+
+        if let Item::SetAuthority(set_authority) = item {
+            if set_authority.authority_type == AUTHORITY_TYPE_ACCOUNT_OWNER
+                && set_authority.old_authority != set_authority.new_authority
+            {
+                let delta = transaction_deltas
+                    .get(&(set_authority.target.as_str(), set_authority.mint.as_str()))
+                    .cloned()
+                    .unwrap_or_default();
+                let balance = parse_raw_amount(&set_authority.raw_account_pre_balance) + delta;
+                let old_owner = balance_key(HOLDER_OWNER, &set_authority.old_authority, &set_authority.mint);
+                let new_owner = balance_key(HOLDER_OWNER, &set_authority.new_authority, &set_authority.mint);
+                store.add(ordinal, old_owner, balance.clone().neg());
+                store.add(ordinal, new_owner, balance);
+            }
+            continue;
+        }
+
+        for delta in balance_deltas(item) {
+            let transaction_delta = transaction_deltas.entry((delta.account, delta.mint)).or_default();
+            *transaction_delta = transaction_delta.clone() + delta.amount.clone();
+            add_balance(&store, ordinal, delta.account, delta.owner, delta.mint, delta.amount);
+        }
+    }
+}
+
+fn add_balance(store: &StoreAddBigInt, ordinal: u64, account: &str, owner: &str, mint: &str, amount: BigInt) {
+    store.add(ordinal, balance_key(HOLDER_ACCOUNT, account, mint), &amount);
+    if !owner.is_empty() {
+        store.add(ordinal, balance_key(HOLDER_OWNER, owner, mint), &amount);
+    }
+}
+
+pub(crate) fn balance_key(holder_type: &str, address: &str, mint: &str) -> String {
+    format!("{}:{}:{}", holder_type, address, mint)
+}
+
+/// Emits, for each token account and owner whose balance changed in the block, its balance before
+/// and after the block, flagging the holders marked by `store_confidential_accounts`.
+#[substreams::handlers::map]
+fn map_balance_changes(
+    deltas: Deltas<DeltaBigInt>,
+    confidential_accounts_store: StoreGetInt64,
+) -> Result<BalanceChanges, Error> {
+    let mut balance_changes: Vec<BalanceChange> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for delta in deltas.deltas {
+        if let Some(position) = positions.get(&delta.key) {
+            balance_changes[*position].new_balance = delta.new_value.to_string();
+            continue;
+        }
+
+        let mut parts = delta.key.splitn(3, ':');
+        let (Some(holder_type), Some(address), Some(mint)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(anyhow::anyhow!("invalid balance key {}", delta.key));
+        };
+
+        positions.insert(delta.key.clone(), balance_changes.len());
+        balance_changes.push(BalanceChange {
+            holder_type: holder_type.to_string(),
+            address: address.to_string(),
+            mint: mint.to_string(),
+            old_balance: delta.old_value.to_string(),
+            new_balance: delta.new_value.to_string(),
+            confidential: confidential_accounts_store.has_last(&delta.key),
+        });
+    }
+
+    Ok(BalanceChanges { balance_changes })
+}
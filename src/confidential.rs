@@ -0,0 +1,76 @@
+use crate::balances::{balance_key, HOLDER_ACCOUNT, HOLDER_OWNER};
+use crate::pb::sf::solana::spl::v1::r#type::{instruction::Item, SplInstructions};
+use substreams::store::{StoreNew, StoreSetIfNotExists, StoreSetIfNotExistsInt64};
+
+const CONFIDENTIAL_TRANSFER_EXTENSION: u8 = 27;
+
+/// Token-2022 confidential transfer instructions moving tokens. They are decoded here as
+/// `TokenInstruction::unpack` only exposes the extension's tag.
+pub enum ConfidentialTransferInstruction {
+    /// Accounts: 0. token account, 1. mint, 2. owner.
+    Deposit { amount: u64, decimals: u8 },
+    /// Accounts: 0. token account, 1. mint, then proof accounts and the owner.
+    Withdraw { amount: u64, decimals: u8 },
+    /// Accounts: 0. source token account, 1. mint, 2. destination token account, then proof accounts
+    /// and the owner. Also decoded from the deprecated `TransferWithFee`.
+    Transfer,
+    /// Accounts: 0. token account, 1. owner.
+    ApplyPendingBalance,
+}
+
+impl ConfidentialTransferInstruction {
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let (&tag, rest) = data.split_first()?;
+        if tag != CONFIDENTIAL_TRANSFER_EXTENSION {
+            return None;
+        }
+
+        let (&sub_tag, rest) = rest.split_first()?;
+        match sub_tag {
+            5 | 6 => {
+                let amount = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+                let decimals = *rest.get(8)?;
+                Some(if sub_tag == 5 {
+                    Self::Deposit { amount, decimals }
+                } else {
+                    Self::Withdraw { amount, decimals }
+                })
+            }
+            7 | 13 => Some(Self::Transfer),
+            8 => Some(Self::ApplyPendingBalance),
+            _ => None,
+        }
+    }
+}
+
+/// Marks the token accounts (`account:<account>:<mint>`) and owners (`owner:<owner>:<mint>`) that took
+/// part in a confidential transfer, keyed like `store_token_balances`, with the slot they were first
+/// seen at. Their public balance no longer reflects all of their tokens.
+#[substreams::handlers::store]
+fn store_confidential_accounts(spl_instructions: SplInstructions, store: StoreSetIfNotExistsInt64) {
+    for (ordinal, instruction) in spl_instructions.instructions.iter().enumerate() {
+        if !instruction.succeeded {
+            continue;
+        }
+
+        let ordinal = ordinal as u64;
+        let slot = instruction.slot as i64;
+        let holders: Vec<(&str, &str, &str)> = match &instruction.item {
+            Some(Item::ConfidentialDeposit(deposit)) => vec![(&deposit.account, &deposit.owner, &deposit.mint)],
+            Some(Item::ConfidentialWithdraw(withdraw)) => vec![(&withdraw.account, &withdraw.owner, &withdraw.mint)],
+            Some(Item::ConfidentialTransfer(transfer)) => vec![
+                (&transfer.from, &transfer.from_owner, &transfer.mint),
+                (&transfer.to, &transfer.to_owner, &transfer.mint),
+            ],
+            Some(Item::ApplyPendingBalance(apply)) => vec![(&apply.account, &apply.owner, &apply.mint)],
+            _ => continue,
+        };
+
+        for (account, owner, mint) in holders {
+            store.set_if_not_exists(ordinal, balance_key(HOLDER_ACCOUNT, account, mint), &slot);
+            if !owner.is_empty() {
+                store.set_if_not_exists(ordinal, balance_key(HOLDER_OWNER, owner, mint), &slot);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::params::TrackedMints;
+use crate::pb::sf::solana::spl::v1::r#type::DecimalsMismatch;
+use crate::token_balance;
+use std::collections::{BTreeMap, BTreeSet};
+use substreams::store::{StoreGet, StoreGetInt64};
+use substreams_solana::block_view::InstructionView;
+use substreams_solana::Address;
+
+/// Resolves the decimals of the mints seen in a block, preferring what the chain says over the
+/// decimals configured through the module's params.
+pub struct MintDecimals<'a> {
+    tracked_mints: &'a TrackedMints,
+    store: &'a StoreGetInt64,
+    mismatches: BTreeMap<String, DecimalsMismatch>,
+    unresolved: BTreeSet<String>,
+}
+
+impl<'a> MintDecimals<'a> {
+    pub fn new(tracked_mints: &'a TrackedMints, store: &'a StoreGetInt64) -> Self {
+        Self {
+            tracked_mints,
+            store,
+            mismatches: BTreeMap::new(),
+            unresolved: BTreeSet::new(),
+        }
+    }
+
+    /// Resolves the decimals of `mint`, in order: the decimals carried by checked instructions, the
+    /// ones recorded in `store_mint_decimals`, the ones reported in the transaction's token balances
+    /// of `account` and finally the configured value. A configured value disagreeing with the chain
+    /// is recorded as a mismatch, and mints whose decimals are found nowhere are recorded as unresolved
+    /// and scaled with 0 decimals.
+    ///
+    /// The decimals carried by instructions of failed transactions are unchecked (a wrong value is
+    /// what may have failed them), so they are ignored and never recorded as a mismatch.
+    pub fn resolve(
+        &mut self,
+        mint: &str,
+        checked_decimals: Option<u8>,
+        instruction: &InstructionView,
+        account: &Address,
+    ) -> u32 {
+        let failed = instruction.meta().err.is_some();
+        let configured = self.tracked_mints.decimals(mint);
+        let Some(chain) = checked_decimals
+            .filter(|_| !failed)
+            .map(|decimals| decimals as u32)
+            .or_else(|| self.store.get_last(mint).map(|decimals| decimals as u32))
+            .or_else(|| token_balance_decimals(instruction, account))
+        else {
+            return configured.unwrap_or_else(|| {
+                self.unresolved.insert(mint.to_string());
+                0
+            });
+        };
+
+        if let Some(configured) = configured.filter(|_| !failed) {
+            if configured != chain {
+                self.mismatches.insert(
+                    mint.to_string(),
+                    DecimalsMismatch {
+                        mint: mint.to_string(),
+                        configured_decimals: configured,
+                        chain_decimals: chain,
+                    },
+                );
+            }
+        }
+
+        chain
+    }
+
+    /// Returns the decimals mismatches and the mints whose decimals could not be resolved.
+    pub fn into_reports(self) -> (Vec<DecimalsMismatch>, Vec<String>) {
+        (
+            self.mismatches.into_values().collect(),
+            self.unresolved.into_iter().collect(),
+        )
+    }
+}
+
+fn token_balance_decimals(instruction: &InstructionView, account: &Address) -> Option<u32> {
+    token_balance(instruction, account)
+        .and_then(|balance| balance.ui_token_amount.as_ref())
+        .map(|ui_amount| ui_amount.decimals)
+}
@@ -0,0 +1,96 @@
+use crate::params::{Params, TrackedMints};
+use crate::pb::sf::solana::spl::v1::r#type::SplParams;
+use crate::ui_amount::UiAmountInstruction;
+use crate::{is_token_program, transactions_owned};
+use std::collections::BTreeSet;
+use substreams::errors::Error;
+use substreams::pb::sf::substreams::index::v1::Keys;
+use substreams_solana::block_view::InstructionView;
+use substreams_solana::pb::sf::solana::r#type::v1::Block;
+use substreams_solana_program_instructions::token_instruction_2022::{AuthorityType, TokenInstruction};
+use substreams_solana_program_instructions::transfer_fee_instruction::TransferFeeInstruction;
+
+/// Key of the blocks touching a tracked mint, the `blockFilter` query of `map_spl_instructions` and its stores.
+const TRACKED_MINT_KEY: &str = "tracked_mint";
+
+/// Indexes blocks by the token programs (`program:<id>`) and mints (`mint:<address>`) their token
+/// transactions touch, failed transactions included. Mints come from the transactions' token
+/// balances and from the token instructions referencing the mint account directly.
+///
+/// Adds the `tracked_mint` key to blocks touching one of the mints tracked by `map_params`, or to
+/// every block with a token instruction when all mints are tracked.
+#[substreams::handlers::map]
+fn index_spl_token(params: SplParams, block: Block) -> Result<Keys, Error> {
+    let params = Params::from(params);
+    let mut keys = BTreeSet::new();
+    let mut tracked = false;
+
+    for (_, confirmed_trx) in transactions_owned(block, true) {
+        let mut mints = BTreeSet::new();
+        if let Some(meta) = &confirmed_trx.meta {
+            for balance in meta.pre_token_balances.iter().chain(meta.post_token_balances.iter()) {
+                mints.insert(balance.mint.clone());
+            }
+        }
+
+        for instruction in confirmed_trx.walk_instructions() {
+            let program_id = instruction.program_id().to_string();
+            if !is_token_program(&program_id) {
+                continue;
+            }
+
+            if let Some(mint) = instruction_mint(&instruction) {
+                mints.insert(mint);
+            }
+            if matches!(params.tracked_mints, TrackedMints::All) {
+                tracked = true;
+            }
+            keys.insert(format!("program:{}", program_id));
+        }
+
+        for mint in mints {
+            tracked |= params.tracked_mints.contains(&mint);
+            keys.insert(format!("mint:{}", mint));
+        }
+    }
+
+    if tracked {
+        keys.insert(TRACKED_MINT_KEY.to_string());
+    }
+
+    Ok(Keys {
+        keys: keys.into_iter().collect(),
+    })
+}
+
+/// Returns the mint of token instructions referencing it as one of their accounts.
+fn instruction_mint(instruction: &InstructionView) -> Option<String> {
+    if UiAmountInstruction::unpack(instruction.data()).is_some() {
+        return instruction.accounts().first().map(|account| account.to_string());
+    }
+
+    let position = match TokenInstruction::unpack(instruction.data()).ok()? {
+        TokenInstruction::InitializeMint { .. }
+        | TokenInstruction::InitializeMint2 { .. }
+        | TokenInstruction::MintTo { .. }
+        | TokenInstruction::MintToChecked { .. } => 0,
+        TokenInstruction::SetAuthority { authority_type, .. } => match authority_type {
+            AuthorityType::AccountOwner | AuthorityType::CloseAccount => return None,
+            _ => 0,
+        },
+        TokenInstruction::InitializeAccount
+        | TokenInstruction::InitializeAccount2 { .. }
+        | TokenInstruction::InitializeAccount3 { .. }
+        | TokenInstruction::TransferChecked { .. }
+        | TokenInstruction::ApproveChecked { .. }
+        | TokenInstruction::Burn { .. }
+        | TokenInstruction::BurnChecked { .. }
+        | TokenInstruction::FreezeAccount
+        | TokenInstruction::ThawAccount
+        | TokenInstruction::TransferFeeExtension(TransferFeeInstruction::TransferCheckedWithFee { .. }) => 1,
+        TokenInstruction::TransferFeeExtension(_) => 0,
+        _ => return None,
+    };
+
+    instruction.accounts().get(position).map(|account| account.to_string())
+}
@@ -1,87 +1,240 @@
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+mod balances;
+mod confidential;
 mod constants;
+mod decimals;
+mod index;
+mod mint_registry;
+mod params;
 mod pb;
+mod reconciliation;
+mod supply;
+mod token_metadata;
+mod transaction_error;
+mod transfer_fee;
+mod ui_amount;
 
+use crate::balances::balance_deltas;
+use crate::confidential::ConfidentialTransferInstruction;
+use crate::decimals::MintDecimals;
+use crate::params::{Params, TrackedMints};
 use crate::pb::sf::solana::spl::v1::r#type::{
-    instruction::Item, Burn, InitializedAccount, Instruction, Mint, SplInstructions, Transfer,
+    instruction::Item, ApplyPendingBalance, Approve, Burn, CloseAccount, ConfidentialDeposit, ConfidentialTransfer,
+    ConfidentialWithdraw, DecodeError, Freeze, HarvestWithheldTokensToMint, InitializedAccount, Instruction, Mint,
+    OwnerResolutionStats, Revoke, SetAuthority, SplInstructions, SplParams, Thaw, Transfer, WithdrawWithheldTokens,
 };
 use crate::pb::sf::substreams::solana::spl::v1::AccountOwner;
-use pb::sf::substreams::solana::v1::Transactions as SolanaTransactions;
+use crate::token_metadata::TokenMetadataInstruction;
+use crate::transaction_error::transaction_error_to_string;
+use crate::transfer_fee::TransferFees;
+use crate::ui_amount::{UiAmountInstruction, UiAmountScales};
 use prost::Message;
+use prost_types::Timestamp;
 use std::collections::{HashMap, HashSet};
 use std::ops::Div;
 use substreams::errors::Error;
 use substreams::pb::sf::substreams::foundational_store::model::v2::ResponseCode;
-use substreams::store::FoundationalStore;
+use substreams::scalar::BigInt;
+use substreams::store::{
+    FoundationalStore, StoreDelete, StoreGet, StoreGetArray, StoreGetInt64, StoreGetString, StoreNew, StoreSet,
+    StoreSetInt64, StoreSetString,
+};
 use substreams_solana::block_view::InstructionView;
-use substreams_solana::pb::sf::solana::r#type::v1::{ConfirmedTransaction, TokenBalance, TransactionStatusMeta};
+use substreams_solana::pb::sf::solana::r#type::v1::{Block, ConfirmedTransaction, TokenBalance};
 use substreams_solana::Address;
-use substreams_solana_program_instructions::token_instruction_2022::TokenInstruction;
+use substreams_solana_program_instructions::option::COption;
+use substreams_solana_program_instructions::token_instruction_2022::{AuthorityType, TokenInstruction};
+use substreams_solana_program_instructions::transfer_fee_instruction::TransferFeeInstruction;
 
 pub const SOLANA_TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const SOLANA_TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
 
 struct OutputInstructions {
     transaction_hash: String,
-    ordinal: i64,
+    context: TransactionContext,
+    position: InstructionPosition,
     instructions: Vec<Instruction>,
+    decode_errors: Vec<DecodeError>,
+    /// Whether the transaction touches a tracked mint, decode failures of other transactions being
+    /// ignored: they are mostly other mints' instructions the decoder does not know.
+    touches_tracked_mint: bool,
 }
 
 impl OutputInstructions {
-    pub fn new(transaction_hash: String) -> Self {
+    pub fn new(transaction_hash: String, context: TransactionContext, touches_tracked_mint: bool) -> Self {
         Self {
             transaction_hash,
-            ordinal: 0,
+            context,
+            touches_tracked_mint,
+            position: InstructionPosition::default(),
             instructions: vec![],
+            decode_errors: vec![],
         }
     }
 
-    pub fn add(&mut self, item: Item) {
+    /// Sets the position of the instruction being processed, used by the following [Self::add]
+    /// and [Self::add_decode_error] calls.
+    pub fn at(&mut self, position: InstructionPosition) {
+        self.position = position;
+    }
+
+    pub fn add_decode_error(&mut self, instruction: &InstructionView, err: &Error) {
+        self.decode_errors.push(DecodeError {
+            transaction_hash: self.transaction_hash.to_string(),
+            instruction_index: self.position.instruction_index,
+            inner_instruction_index: self.position.inner_instruction_index,
+            data: instruction.data().clone(),
+            error: err.to_string(),
+        });
+    }
+
+    pub fn add(&mut self, token_program: &str, item: Item) {
         self.instructions.push(Instruction {
             transaction_hash: self.transaction_hash.to_string(),
-            instruction_id: self.transaction_hash.to_string() + "-" + &self.ordinal.to_string(),
+            instruction_id: self.position.instruction_id(&self.transaction_hash),
+            instruction_index: self.position.instruction_index,
+            inner_instruction_index: self.position.inner_instruction_index,
+            stack_height: self.position.stack_height,
+            token_program: token_program.to_string(),
+            slot: self.context.slot,
+            block_time: self.context.block_time,
+            transaction_index: self.context.transaction_index,
+            fee_payer: self.context.fee_payer.clone(),
+            signers: self.context.signers.clone(),
+            succeeded: self.context.error.is_none(),
+            error: self.context.error.clone().unwrap_or_default(),
             item: Some(item),
         });
+    }
+}
+
+/// Position of an instruction within its transaction.
+#[derive(Default)]
+struct InstructionPosition {
+    instruction_index: u32,
+    inner_instruction_index: Option<u32>,
+    stack_height: u32,
+}
+
+impl InstructionPosition {
+    /// Builds the instruction id, `<trx_hash>-<instruction_index>` for top-level instructions and
+    /// `<trx_hash>-<instruction_index>-<inner_instruction_index>` for inner ones. It only depends on
+    /// the instruction's position so it is stable whatever gets extracted from the transaction.
+    fn instruction_id(&self, transaction_hash: &str) -> String {
+        match self.inner_instruction_index {
+            None => format!("{}-{}", transaction_hash, self.instruction_index),
+            Some(inner_index) => format!("{}-{}-{}", transaction_hash, self.instruction_index, inner_index),
+        }
+    }
+}
+
+/// Block and transaction level information copied on each emitted [Instruction].
+struct TransactionContext {
+    slot: u64,
+    block_time: Option<Timestamp>,
+    transaction_index: u32,
+    fee_payer: String,
+    signers: Vec<String>,
+    error: Option<String>,
+}
+
+impl TransactionContext {
+    fn new(block: &BlockHeader, transaction_index: usize, trx: &ConfirmedTransaction) -> Self {
+        let message = trx.transaction.as_ref().unwrap().message.as_ref().unwrap();
+        let num_signers = message
+            .header
+            .as_ref()
+            .map(|header| header.num_required_signatures as usize)
+            .unwrap_or(0);
 
-        self.ordinal += 1;
+        Self {
+            slot: block.slot,
+            block_time: block.block_time,
+            transaction_index: transaction_index as u32,
+            fee_payer: message
+                .account_keys
+                .first()
+                .map(|key| bs58::encode(key).into_string())
+                .unwrap_or_default(),
+            signers: message
+                .account_keys
+                .iter()
+                .take(num_signers)
+                .map(|key| bs58::encode(key).into_string())
+                .collect(),
+            error: trx
+                .meta
+                .as_ref()
+                .and_then(|meta| meta.err.as_ref())
+                .map(transaction_error_to_string),
+        }
+    }
+}
+
+/// The parts of the [Block] still needed once its transactions have been taken.
+struct BlockHeader {
+    slot: u64,
+    block_time: Option<Timestamp>,
+}
+
+impl From<&Block> for BlockHeader {
+    fn from(block: &Block) -> Self {
+        Self {
+            slot: block.slot,
+            block_time: block.block_time.as_ref().map(|time| Timestamp {
+                seconds: time.timestamp,
+                nanos: 0,
+            }),
+        }
     }
 }
 
 #[substreams::handlers::map]
 fn map_spl_instructions(
-    params: String,
-    transactions: SolanaTransactions,
+    params: SplParams,
+    block: Block,
     foundational_store: FoundationalStore,
+    decimals_store: StoreGetInt64,
+    owner_changes_store: StoreGetString,
+    ui_amount_configs_store: StoreGetArray<String>,
+    transfer_fee_configs_store: StoreGetArray<String>,
 ) -> Result<SplInstructions, Error> {
     let mut instructions: Vec<Instruction> = vec![];
+    let mut decode_errors: Vec<DecodeError> = vec![];
+    let mut token_balance_owners = HashMap::<(String, String), String>::new();
 
-    let mut spl_token_address = String::new();
-    let mut spl_token_decimal = 0;
+    let params = Params::from(params);
+    let mut mint_decimals = MintDecimals::new(&params.tracked_mints, &decimals_store);
+    let transfer_fees = TransferFees::new(&transfer_fee_configs_store);
 
-    for param in params.split('|') {
-        let parts: Vec<&str> = param.split('=').collect();
-        if parts.len() == 2 {
-            match parts[0] {
-                "spl_token_address" => spl_token_address = parts[1].to_string(),
-                "spl_token_decimal" => spl_token_decimal = parts[1].parse().unwrap(),
-                _ => {}
-            }
-        }
-    }
-
-    for confirmed_trx in transactions_owned(transactions) {
+    let header = BlockHeader::from(&block);
+    for (transaction_index, confirmed_trx) in transactions_owned(block, params.include_failed_transactions) {
         let hash = bs58::encode(confirmed_trx.hash()).into_string();
+        let context = TransactionContext::new(&header, transaction_index, &confirmed_trx);
+
+        collect_token_balance_owners(&hash, &confirmed_trx, &mut token_balance_owners);
 
-        let mut output_instructions = OutputInstructions::new(hash.clone());
+        let mut output_instructions = OutputInstructions::new(
+            hash.clone(),
+            context,
+            touches_tracked_mint(&confirmed_trx, &params.tracked_mints),
+        );
 
-        for instruction in confirmed_trx.compiled_instructions() {
+        for (index, instruction) in confirmed_trx.compiled_instructions().enumerate() {
             process_instruction(
                 &mut output_instructions,
-                &spl_token_address,
-                spl_token_decimal,
+                &params,
+                &mut mint_decimals,
+                index as u32,
                 &instruction,
             );
         }
 
+        transfer_fees.apply(&mut output_instructions.instructions);
+        set_withheld_withdrawal_amounts(&confirmed_trx, &mut output_instructions.instructions);
         instructions.extend(output_instructions.instructions);
+        decode_errors.extend(output_instructions.decode_errors);
     }
 
     let mut accounts_to_lookup = HashSet::<String>::new();
@@ -99,47 +252,375 @@ fn map_spl_instructions(
                 Item::Burn(burn) => {
                     accounts_to_lookup.insert(burn.from.clone());
                 }
+                Item::Approve(approve) => {
+                    accounts_to_lookup.insert(approve.source.clone());
+                }
+                Item::Revoke(revoke) => {
+                    accounts_to_lookup.insert(revoke.source.clone());
+                }
+                Item::CloseAccount(close_account) => {
+                    accounts_to_lookup.insert(close_account.account.clone());
+                }
+                Item::Freeze(freeze) => {
+                    accounts_to_lookup.insert(freeze.account.clone());
+                }
+                Item::Thaw(thaw) => {
+                    accounts_to_lookup.insert(thaw.account.clone());
+                }
+                Item::WithdrawWithheldTokens(withdraw) => {
+                    accounts_to_lookup.insert(withdraw.destination.clone());
+                }
+                Item::ConfidentialDeposit(deposit) => {
+                    accounts_to_lookup.insert(deposit.account.clone());
+                }
+                Item::ConfidentialWithdraw(withdraw) => {
+                    accounts_to_lookup.insert(withdraw.account.clone());
+                }
+                Item::ConfidentialTransfer(transfer) => {
+                    accounts_to_lookup.insert(transfer.from.clone());
+                    accounts_to_lookup.insert(transfer.to.clone());
+                }
+                Item::ApplyPendingBalance(apply) => {
+                    accounts_to_lookup.insert(apply.account.clone());
+                }
                 _ => {}
             }
         }
     }
 
-    let owners = resolve_account_owners(&foundational_store, &accounts_to_lookup);
+    let mut owner_resolution_stats = OwnerResolutionStats::default();
+    let owners = resolve_account_owners(&foundational_store, &accounts_to_lookup, &mut owner_resolution_stats);
+    let mut account_owners = AccountOwners::new(owners, &owner_changes_store, token_balance_owners);
 
     for instruction in &mut instructions {
+        let trx_hash = &instruction.transaction_hash;
+        // Failed transactions do not change account owners.
+        let succeeded = instruction.succeeded;
         if let Some(ref mut item) = instruction.item {
             match item {
                 Item::Transfer(ref mut transfer) => {
-                    if let Some(from_owner) = owners.get(&transfer.from) {
-                        transfer.from_owner = from_owner.clone();
-                    }
-                    if let Some(to_owner) = owners.get(&transfer.to) {
-                        transfer.to_owner = to_owner.clone();
-                    }
+                    account_owners.resolve(
+                        trx_hash,
+                        &transfer.from,
+                        &mut transfer.from_owner,
+                        &mut transfer.from_owner_source,
+                    );
+                    account_owners.resolve(
+                        trx_hash,
+                        &transfer.to,
+                        &mut transfer.to_owner,
+                        &mut transfer.to_owner_source,
+                    );
                 }
                 Item::Mint(ref mut mint) => {
-                    if let Some(to_owner) = owners.get(&mint.to) {
-                        mint.to_owner = to_owner.clone();
-                    }
+                    account_owners.resolve(trx_hash, &mint.to, &mut mint.to_owner, &mut mint.to_owner_source);
                 }
                 Item::Burn(ref mut burn) => {
-                    if let Some(from_owner) = owners.get(&burn.from) {
-                        burn.from_owner = from_owner.clone();
+                    account_owners.resolve(trx_hash, &burn.from, &mut burn.from_owner, &mut burn.from_owner_source);
+                }
+                Item::Approve(ref mut approve) => {
+                    account_owners.resolve(trx_hash, &approve.source, &mut approve.owner, &mut approve.owner_source);
+                }
+                Item::Revoke(ref mut revoke) => {
+                    account_owners.resolve(trx_hash, &revoke.source, &mut revoke.owner, &mut revoke.owner_source);
+                }
+                Item::CloseAccount(ref mut close_account) => {
+                    account_owners.resolve(
+                        trx_hash,
+                        &close_account.account,
+                        &mut close_account.owner,
+                        &mut close_account.owner_source,
+                    );
+                }
+                Item::Freeze(ref mut freeze) => {
+                    account_owners.resolve(trx_hash, &freeze.account, &mut freeze.owner, &mut freeze.owner_source);
+                }
+                Item::Thaw(ref mut thaw) => {
+                    account_owners.resolve(trx_hash, &thaw.account, &mut thaw.owner, &mut thaw.owner_source);
+                }
+                Item::WithdrawWithheldTokens(ref mut withdraw) => {
+                    account_owners.resolve(
+                        trx_hash,
+                        &withdraw.destination,
+                        &mut withdraw.destination_owner,
+                        &mut withdraw.destination_owner_source,
+                    );
+                }
+                Item::HarvestWithheldTokensToMint(_) => {}
+                Item::ConfidentialDeposit(ref mut deposit) => {
+                    account_owners.resolve(
+                        trx_hash,
+                        &deposit.account,
+                        &mut deposit.owner,
+                        &mut deposit.owner_source,
+                    );
+                }
+                Item::ConfidentialWithdraw(ref mut withdraw) => {
+                    account_owners.resolve(
+                        trx_hash,
+                        &withdraw.account,
+                        &mut withdraw.owner,
+                        &mut withdraw.owner_source,
+                    );
+                }
+                Item::ConfidentialTransfer(ref mut transfer) => {
+                    account_owners.resolve(
+                        trx_hash,
+                        &transfer.from,
+                        &mut transfer.from_owner,
+                        &mut transfer.from_owner_source,
+                    );
+                    account_owners.resolve(
+                        trx_hash,
+                        &transfer.to,
+                        &mut transfer.to_owner,
+                        &mut transfer.to_owner_source,
+                    );
+                }
+                Item::ApplyPendingBalance(ref mut apply) => {
+                    account_owners.resolve(trx_hash, &apply.account, &mut apply.owner, &mut apply.owner_source);
+                }
+                Item::SetAuthority(set_authority) => {
+                    if succeeded && set_authority.authority_type == AUTHORITY_TYPE_ACCOUNT_OWNER {
+                        account_owners.change(
+                            &set_authority.target,
+                            &set_authority.new_authority,
+                            OwnerSource::OwnerChange,
+                        );
+                    }
+                }
+                Item::InitializedAccount(initialized_account) => {
+                    if !succeeded {
+                        continue;
                     }
+                    account_owners.change(
+                        &initialized_account.account,
+                        &initialized_account.owner,
+                        OwnerSource::InitializedAccount,
+                    );
+                }
+            }
+        }
+    }
+
+    UiAmountScales::new(&ui_amount_configs_store).apply(&mut instructions);
+
+    let (decimals_mismatches, unresolved_decimals) = mint_decimals.into_reports();
+    Ok(SplInstructions {
+        instructions,
+        decode_errors,
+        decimals_mismatches,
+        unresolved_decimals,
+        all_mints_tracked: matches!(params.tracked_mints, TrackedMints::All),
+        tracked_mints: params.tracked_mints.addresses(),
+        owner_resolution_stats: Some(OwnerResolutionStats {
+            fallback_resolved: account_owners.fallback_resolved,
+            ..owner_resolution_stats
+        }),
+    })
+}
+
+/// Records the decimals of the tracked mints, from their initialization and checked instructions.
+#[substreams::handlers::store]
+fn store_mint_decimals(params: SplParams, block: Block, store: StoreSetInt64) {
+    let tracked_mints = Params::from(params).tracked_mints;
+    for (_, confirmed_trx) in transactions_owned(block, false) {
+        for instruction in confirmed_trx.walk_instructions() {
+            if !is_token_program(&instruction.program_id().to_string()) {
+                continue;
+            }
+
+            let Ok(token_instruction) = TokenInstruction::unpack(instruction.data()) else {
+                continue;
+            };
+
+            let (mint, decimals) = match token_instruction {
+                TokenInstruction::InitializeMint { decimals, .. }
+                | TokenInstruction::InitializeMint2 { decimals, .. } => (&instruction.accounts()[0], decimals),
+                TokenInstruction::TransferChecked { decimals, .. }
+                | TokenInstruction::ApproveChecked { decimals, .. }
+                | TokenInstruction::BurnChecked { decimals, .. } => (&instruction.accounts()[1], decimals),
+                TokenInstruction::MintToChecked { decimals, .. } => (&instruction.accounts()[0], decimals),
+                TokenInstruction::TransferFeeExtension(TransferFeeInstruction::TransferCheckedWithFee {
+                    decimals,
+                    ..
+                }) => (&instruction.accounts()[1], decimals),
+                _ => continue,
+            };
+            if !tracked_mints.contains(&mint.to_string()) {
+                continue;
+            }
+
+            store.set(0, mint.to_string(), &(decimals as i64));
+        }
+    }
+}
+
+/// Records the owner changes of the token accounts of the tracked mints. Accounts whose mint the
+/// transaction's token balances do not tell are kept, not to miss a tracked one.
+#[substreams::handlers::store]
+fn store_account_owner_changes(params: SplParams, block: Block, store: StoreSetString) {
+    let tracked_mints = Params::from(params).tracked_mints;
+    for (_, confirmed_trx) in transactions_owned(block, false) {
+        for instruction in confirmed_trx.walk_instructions() {
+            if !is_token_program(&instruction.program_id().to_string()) {
+                continue;
+            }
+
+            let Ok(token_instruction) = TokenInstruction::unpack(instruction.data()) else {
+                continue;
+            };
+            let Some(account) = instruction.accounts().into_iter().next() else {
+                continue;
+            };
+            if token_account_mint(&instruction, &account).is_some_and(|mint| !tracked_mints.contains(&mint)) {
+                continue;
+            }
+
+            match token_instruction {
+                TokenInstruction::SetAuthority {
+                    authority_type: AuthorityType::AccountOwner,
+                    new_authority: COption::Some(new_owner),
+                } => {
+                    store.set(
+                        0,
+                        owner_change_key(&account.to_string()),
+                        &bs58::encode(new_owner).into_string(),
+                    );
+                }
+                TokenInstruction::InitializeAccount
+                | TokenInstruction::InitializeAccount2 { .. }
+                | TokenInstruction::InitializeAccount3 { .. }
+                | TokenInstruction::CloseAccount => {
+                    // A closed then re-initialized account gets its owner from the foundational store again.
+                    store.delete_prefix(0, &owner_change_key(&account.to_string()));
                 }
                 _ => {}
             }
         }
     }
+}
+
+/// Key of the owner change of `account` in `store_account_owner_changes`, terminated so that deleting
+/// it by prefix does not delete the changes of longer addresses starting with the same characters.
+fn owner_change_key(account: &str) -> String {
+    format!("{}:owner", account)
+}
+
+/// Where the owner of a token account was resolved from.
+#[derive(Clone, Copy)]
+enum OwnerSource {
+    FoundationalStore,
+    OwnerChange,
+    InitializedAccount,
+    TokenBalance,
+}
+
+impl OwnerSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OwnerSource::FoundationalStore => "foundational_store",
+            OwnerSource::OwnerChange => "owner_change",
+            OwnerSource::InitializedAccount => "initialized_account",
+            OwnerSource::TokenBalance => "token_balance",
+        }
+    }
+}
+
+/// Resolves token account owners, giving precedence to `AccountOwner` authority changes over the
+/// owner recorded at initialization in the foundational store. Changes from previous blocks come
+/// from `store_account_owner_changes` while changes within the block, as well as accounts
+/// initialized within the block, are applied as instructions are walked in order, so an
+/// instruction is attributed to the owner at the time it executed. Accounts unknown to all of
+/// those fall back to the owner reported in the transaction's token balances.
+struct AccountOwners<'a> {
+    initialized: HashMap<String, String>,
+    changes_store: &'a StoreGetString,
+    block_changes: HashMap<String, (String, OwnerSource)>,
+    token_balance_owners: HashMap<(String, String), String>,
+    /// Number of owners resolved from in-block initializations or transaction token balances.
+    fallback_resolved: u32,
+}
+
+impl<'a> AccountOwners<'a> {
+    fn new(
+        initialized: HashMap<String, String>,
+        changes_store: &'a StoreGetString,
+        token_balance_owners: HashMap<(String, String), String>,
+    ) -> Self {
+        Self {
+            initialized,
+            changes_store,
+            block_changes: HashMap::new(),
+            token_balance_owners,
+            fallback_resolved: 0,
+        }
+    }
+
+    fn get(&self, trx_hash: &str, account: &String) -> Option<(String, OwnerSource)> {
+        if let Some(owner) = self.block_changes.get(account) {
+            return Some(owner.clone());
+        }
+
+        if let Some(owner) = self.changes_store.get_first(owner_change_key(account)) {
+            return Some((owner, OwnerSource::OwnerChange));
+        }
+
+        if let Some(owner) = self.initialized.get(account) {
+            return Some((owner.clone(), OwnerSource::FoundationalStore));
+        }
+
+        self.token_balance_owners
+            .get(&(trx_hash.to_string(), account.clone()))
+            .map(|owner| (owner.clone(), OwnerSource::TokenBalance))
+    }
+
+    /// Sets `owner` and `source` to the resolved owner of `account`, leaving them untouched when unknown.
+    fn resolve(&mut self, trx_hash: &str, account: &String, owner: &mut String, source: &mut String) {
+        if let Some((resolved, resolved_from)) = self.get(trx_hash, account) {
+            if matches!(
+                resolved_from,
+                OwnerSource::InitializedAccount | OwnerSource::TokenBalance
+            ) {
+                self.fallback_resolved += 1;
+            }
+            *owner = resolved;
+            *source = resolved_from.as_str().to_string();
+        }
+    }
+
+    fn change(&mut self, account: &str, owner: &str, source: OwnerSource) {
+        self.block_changes
+            .insert(account.to_string(), (owner.to_string(), source));
+    }
+}
+
+/// Records the owners reported in the transaction's pre and post token balances, keyed by
+/// transaction hash and token account.
+fn collect_token_balance_owners(
+    trx_hash: &str,
+    trx: &ConfirmedTransaction,
+    owners: &mut HashMap<(String, String), String>,
+) {
+    let Some(meta) = &trx.meta else {
+        return;
+    };
+
+    for balance in meta.pre_token_balances.iter().chain(meta.post_token_balances.iter()) {
+        if balance.owner.is_empty() {
+            continue;
+        }
 
-    Ok(SplInstructions { instructions })
+        let account = trx.account_at(balance.account_index as u8).to_string();
+        owners.insert((trx_hash.to_string(), account), balance.owner.clone());
+    }
 }
 
 fn resolve_account_owners(
     foundational_store: &FoundationalStore,
     accounts: &HashSet<String>,
+    stats: &mut OwnerResolutionStats,
 ) -> HashMap<String, String> {
-
     let mut results = HashMap::with_capacity(accounts.len());
     if accounts.is_empty() {
         return results;
@@ -149,19 +630,26 @@ fn resolve_account_owners(
         .iter()
         .filter_map(|account| bs58::decode(account).into_vec().ok())
         .collect();
+    stats.lookups += account_bytes.len() as u32;
 
     let resp = foundational_store.get(&account_bytes);
     for queried_entry in resp.entries {
         if queried_entry.code != ResponseCode::Found as i32 {
+            stats.not_found += 1;
             continue;
         }
+        stats.found += 1;
+
         let Some(entry) = &queried_entry.entry else {
+            stats.decode_failures += 1;
             continue;
         };
         let Some(value) = &entry.value else {
+            stats.decode_failures += 1;
             continue;
         };
         let Ok(account_owner) = AccountOwner::decode(value.value.as_slice()) else {
+            stats.decode_failures += 1;
             continue;
         };
 
@@ -171,185 +659,496 @@ fn resolve_account_owners(
         results.insert(account_b58, owner_b58);
     }
 
-
     results
 }
 
-/// Iterates over successful transactions in given block and take ownership.
-fn transactions_owned(transactions: SolanaTransactions) -> impl Iterator<Item = ConfirmedTransaction> {
-    transactions.transactions.into_iter().filter(|trx| -> bool {
-        if let Some(meta) = &trx.meta {
-            return meta.err.is_none();
-        }
-        false
-    })
+/// Iterates over transactions referencing a token program in given block and take ownership, along
+/// with the index of each transaction within the block. Failed transactions are skipped unless
+/// `include_failed` is set.
+pub(crate) fn transactions_owned(
+    block: Block,
+    include_failed: bool,
+) -> impl Iterator<Item = (usize, ConfirmedTransaction)> {
+    let token_programs: Vec<Vec<u8>> = [SOLANA_TOKEN_PROGRAM, SOLANA_TOKEN_2022_PROGRAM]
+        .iter()
+        .map(|program| bs58::decode(program).into_vec().unwrap())
+        .collect();
+
+    block
+        .transactions
+        .into_iter()
+        .enumerate()
+        .filter(move |(_, trx)| -> bool {
+            let Some(meta) = &trx.meta else {
+                return false;
+            };
+            if meta.err.is_some() && !include_failed {
+                return false;
+            }
+
+            trx.resolved_accounts()
+                .iter()
+                .any(|account| token_programs.contains(account))
+        })
 }
 
 fn process_instruction(
     output: &mut OutputInstructions,
-    spl_token_address: &str,
-    spl_token_decimal: i32,
+    params: &Params,
+    mint_decimals: &mut MintDecimals,
+    instruction_index: u32,
     compile_instruction: &InstructionView,
 ) {
     let trx_hash = &bs58::encode(compile_instruction.transaction().hash()).into_string();
-    match compile_instruction.program_id().to_string().as_ref() {
-        SOLANA_TOKEN_PROGRAM => {
-            match process_token_instruction(
-                output,
-                spl_token_address,
-                spl_token_decimal,
-                compile_instruction,
-                compile_instruction.meta(),
-            ) {
-                Err(err) => {
-                    panic!("trx_hash {} process token instructions: {}", trx_hash, err);
-                }
-                _ => {}
-            }
+    let program_id = compile_instruction.program_id().to_string();
+    if !is_token_program(&program_id) {
+        process_inner_instruction(
+            compile_instruction,
+            params,
+            mint_decimals,
+            instruction_index,
+            trx_hash,
+            output,
+        );
+        return;
+    }
+
+    output.at(InstructionPosition {
+        instruction_index,
+        inner_instruction_index: None,
+        stack_height: 1,
+    });
+    if let Err(err) = process_token_instruction(
+        output,
+        &program_id,
+        &params.tracked_mints,
+        mint_decimals,
+        compile_instruction,
+    ) {
+        if !output.touches_tracked_mint {
+            return;
         }
-        _ => {
-            process_inner_instruction(
-                compile_instruction,
-                spl_token_address,
-                spl_token_decimal,
-                trx_hash,
-                compile_instruction.meta(),
-                output,
-            );
+        if params.strict {
+            panic!("trx_hash {} process token instructions: {}", trx_hash, err);
         }
+        output.add_decode_error(compile_instruction, &err);
     }
 }
 
 fn process_inner_instruction(
     compile_instruction: &InstructionView,
-    spl_token_address: &str,
-    spl_token_decimal: i32,
+    params: &Params,
+    mint_decimals: &mut MintDecimals,
+    instruction_index: u32,
     trx_hash: &String,
-    meta: &TransactionStatusMeta,
     output: &mut OutputInstructions,
 ) {
-    for inner in compile_instruction.inner_instructions() {
-        match inner.program_id().to_string().as_ref() {
-            SOLANA_TOKEN_PROGRAM => {
-                match process_token_instruction(output, spl_token_address, spl_token_decimal, &inner, meta) {
-                    Err(err) => {
-                        panic!("trx_hash {} process token instructions {}", trx_hash, err);
-                    }
-                    _ => {}
-                }
+    for (inner_index, inner) in compile_instruction.inner_instructions().enumerate() {
+        let program_id = inner.program_id().to_string();
+        if !is_token_program(&program_id) {
+            continue;
+        }
+
+        output.at(InstructionPosition {
+            instruction_index,
+            inner_instruction_index: Some(inner_index as u32),
+            stack_height: inner.stack_height(),
+        });
+        if let Err(err) = process_token_instruction(output, &program_id, &params.tracked_mints, mint_decimals, &inner) {
+            if !output.touches_tracked_mint {
+                continue;
             }
-            _ => {}
+            if params.strict {
+                panic!("trx_hash {} process token instructions {}", trx_hash, err);
+            }
+            output.add_decode_error(&inner, &err);
         }
     }
 }
 
+/// Tells whether the transaction's pre or post token balances hold one of the tracked mints.
+fn touches_tracked_mint(trx: &ConfirmedTransaction, tracked_mints: &TrackedMints) -> bool {
+    let Some(meta) = &trx.meta else {
+        return false;
+    };
+
+    meta.pre_token_balances
+        .iter()
+        .chain(meta.post_token_balances.iter())
+        .any(|balance| tracked_mints.contains(&balance.mint))
+}
+
+/// Returns true for both the legacy SPL Token program and the Token-2022 program, the
+/// instruction layout of the latter being a superset of the former.
+pub(crate) fn is_token_program(program_id: &str) -> bool {
+    program_id == SOLANA_TOKEN_PROGRAM || program_id == SOLANA_TOKEN_2022_PROGRAM
+}
+
 fn process_token_instruction(
     output: &mut OutputInstructions,
-    spl_token_address: &str,
-    spl_token_decimal: i32,
+    token_program: &str,
+    tracked_mints: &TrackedMints,
+    mint_decimals: &mut MintDecimals,
     instruction: &InstructionView,
-    meta: &TransactionStatusMeta,
 ) -> Result<(), Error> {
-    match TokenInstruction::unpack(&instruction.data()) {
+    // Interest rate and UI amount multiplier changes only affect UI amounts, see `store_ui_amount_configs`.
+    if UiAmountInstruction::unpack(instruction.data()).is_some() {
+        return Ok(());
+    }
+    // Token metadata is extracted by `map_token_metadata`.
+    if TokenMetadataInstruction::unpack(instruction.data()).is_some() {
+        return Ok(());
+    }
+
+    match TokenInstruction::unpack(instruction.data()) {
         Err(err) => {
             return Err(anyhow::anyhow!("unpacking token instruction: {}", err));
         }
         Ok(token_instruction) => match token_instruction {
             #[allow(deprecated)]
             TokenInstruction::Transfer { amount: amt } => {
-                let authority = &instruction.accounts()[2];
-
-                // let authority = &accounts[inst_accounts[2] as usize];
-                if is_token_transfer(spl_token_address, &meta.pre_token_balances, authority) {
-                    let source = &instruction.accounts()[0];
-                    // let source = &accounts[inst_accounts[0] as usize];
-                    let destination = &instruction.accounts()[1];
-                    // let destination = &accounts[inst_accounts[1] as usize];
-
-                    output.add(Item::Transfer(Transfer {
-                        from: source.to_string(),
-                        to: destination.to_string(),
-                        amount: amount_to_decimals(amt as f64, spl_token_decimal as f64),
-                        from_owner: String::new(),
-                        to_owner: String::new(),
-                    }));
+                let source = &instruction_account(instruction, 0)?;
+                let Some(mint) = token_account_mint(instruction, source) else {
+                    return Ok(());
+                };
+
+                add_transfer(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    mint,
+                    TransferAccounts {
+                        destination: 1,
+                        authority: 2,
+                    },
+                    amt,
+                    0,
+                    None,
+                )?;
+            }
+            TokenInstruction::TransferChecked { amount: amt, decimals } => {
+                add_transfer(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    instruction_account(instruction, 1)?.to_string(),
+                    TransferAccounts {
+                        destination: 2,
+                        authority: 3,
+                    },
+                    amt,
+                    0,
+                    Some(decimals),
+                )?;
+            }
+
+            TokenInstruction::TransferFeeExtension(transfer_fee_instruction) => match transfer_fee_instruction {
+                TransferFeeInstruction::TransferCheckedWithFee {
+                    amount: amt,
+                    decimals,
+                    fee,
+                } => {
+                    add_transfer(
+                        output,
+                        token_program,
+                        tracked_mints,
+                        mint_decimals,
+                        instruction,
+                        instruction_account(instruction, 1)?.to_string(),
+                        TransferAccounts {
+                            destination: 2,
+                            authority: 3,
+                        },
+                        amt,
+                        fee,
+                        Some(decimals),
+                    )?;
+                }
+                TransferFeeInstruction::WithdrawWithheldTokensFromMint => {
+                    add_withdraw_withheld_tokens(
+                        output,
+                        token_program,
+                        tracked_mints,
+                        mint_decimals,
+                        instruction,
+                        vec![],
+                    )?;
+                }
+                TransferFeeInstruction::WithdrawWithheldTokensFromAccounts { num_token_accounts } => {
+                    let accounts = instruction.accounts();
+                    let sources = &accounts[accounts.len().saturating_sub(num_token_accounts as usize)..];
+                    add_withdraw_withheld_tokens(
+                        output,
+                        token_program,
+                        tracked_mints,
+                        mint_decimals,
+                        instruction,
+                        sources.iter().map(|account| account.to_string()).collect(),
+                    )?;
+                }
+                TransferFeeInstruction::HarvestWithheldTokensToMint => {
+                    let mint = instruction_account(instruction, 0)?.to_string();
+                    if !tracked_mints.contains(&mint) {
+                        return Ok(());
+                    }
+
+                    output.add(
+                        token_program,
+                        Item::HarvestWithheldTokensToMint(HarvestWithheldTokensToMint {
+                            source_accounts: instruction
+                                .accounts()
+                                .iter()
+                                .skip(1)
+                                .map(|account| account.to_string())
+                                .collect(),
+                            mint,
+                        }),
+                    );
+                }
+                _ => {}
+            },
+
+            TokenInstruction::ConfidentialTransferExtension => {
+                if let Some(confidential_instruction) = ConfidentialTransferInstruction::unpack(instruction.data()) {
+                    add_confidential_transfer(
+                        output,
+                        token_program,
+                        tracked_mints,
+                        mint_decimals,
+                        instruction,
+                        confidential_instruction,
+                    )?;
+                }
+            }
+
+            TokenInstruction::MintTo { amount: amt } => {
+                add_mint_to(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    amt,
+                    None,
+                )?;
+            }
+            TokenInstruction::MintToChecked { amount: amt, decimals } => {
+                add_mint_to(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    amt,
+                    Some(decimals),
+                )?;
+            }
+
+            TokenInstruction::Burn { amount: amt } => {
+                add_burn(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    amt,
+                    None,
+                )?;
+            }
+            TokenInstruction::BurnChecked { amount: amt, decimals } => {
+                add_burn(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    amt,
+                    Some(decimals),
+                )?;
+            }
+
+            TokenInstruction::Approve { amount: amt } => {
+                let source = &instruction_account(instruction, 0)?;
+                let Some(mint) = token_account_mint(instruction, source) else {
+                    return Ok(());
+                };
+
+                add_approve(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    mint,
+                    &instruction_account(instruction, 1)?,
+                    amt,
+                    None,
+                )?;
+            }
+            TokenInstruction::ApproveChecked { amount: amt, decimals } => {
+                add_approve(
+                    output,
+                    token_program,
+                    tracked_mints,
+                    mint_decimals,
+                    instruction,
+                    instruction_account(instruction, 1)?.to_string(),
+                    &instruction_account(instruction, 2)?,
+                    amt,
+                    Some(decimals),
+                )?;
+            }
+            TokenInstruction::Revoke => {
+                let source = &instruction_account(instruction, 0)?;
+                let Some(mint) = token_account_mint(instruction, source) else {
+                    return Ok(());
+                };
+                if !tracked_mints.contains(&mint) {
+                    return Ok(());
                 }
+
+                output.add(
+                    token_program,
+                    Item::Revoke(Revoke {
+                        source: source.to_string(),
+                        owner: String::new(),
+                        owner_source: String::new(),
+                        mint,
+                    }),
+                );
             }
 
-            TokenInstruction::TransferChecked { amount: amt, .. } => {
-                let mint = &instruction.accounts()[1];
-                // let mint = &accounts[inst_accounts[1] as usize];
-                if mint.to_string() == spl_token_address {
-                    let source = &instruction.accounts()[0];
-                    // let source = &accounts[inst_accounts[0] as usize];
-                    let destination = &instruction.accounts()[2];
-                    // let destination = &accounts[inst_accounts[2] as usize];
-
-                    output.add(Item::Transfer(Transfer {
-                        from: source.to_string(),
-                        to: destination.to_string(),
-                        amount: amount_to_decimals(amt as f64, spl_token_decimal as f64),
-                        from_owner: String::new(),
-                        to_owner: String::new(),
-                    }));
+            TokenInstruction::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                let target = &instruction_account(instruction, 0)?;
+                let mint = match authority_type {
+                    AuthorityType::AccountOwner | AuthorityType::CloseAccount => {
+                        let Some(mint) = token_account_mint(instruction, target) else {
+                            return Ok(());
+                        };
+                        mint
+                    }
+                    _ => target.to_string(),
+                };
+                if !tracked_mints.contains(&mint) {
+                    return Ok(());
                 }
+
+                output.add(
+                    token_program,
+                    Item::SetAuthority(SetAuthority {
+                        target: target.to_string(),
+                        authority_type: authority_type_name(&authority_type).to_string(),
+                        old_authority: instruction_account(instruction, 1)?.to_string(),
+                        new_authority: match new_authority {
+                            COption::Some(authority) => bs58::encode(authority).into_string(),
+                            COption::None => String::new(),
+                        },
+                        raw_account_pre_balance: match authority_type {
+                            AuthorityType::AccountOwner => token_pre_balance(instruction, target).to_string(),
+                            _ => String::new(),
+                        },
+                        mint,
+                    }),
+                );
             }
 
-            TokenInstruction::MintTo { amount: amt } | TokenInstruction::MintToChecked { amount: amt, .. } => {
-                let mint = &instruction.accounts()[0];
-                if mint.to_string().as_str() != spl_token_address {
+            TokenInstruction::CloseAccount => {
+                let account = &instruction_account(instruction, 0)?;
+                let Some(mint) = token_account_mint(instruction, account) else {
+                    return Ok(());
+                };
+                if !tracked_mints.contains(&mint) {
                     return Ok(());
                 }
 
-                let account_to = &instruction.accounts()[1];
-                output.add(Item::Mint(Mint {
-                    to: account_to.to_string(),
-                    amount: amount_to_decimals(amt as f64, spl_token_decimal as f64),
-                    to_owner: String::new(),
-                }));
+                output.add(
+                    token_program,
+                    Item::CloseAccount(CloseAccount {
+                        account: account.to_string(),
+                        destination: instruction_account(instruction, 1)?.to_string(),
+                        owner: String::new(),
+                        owner_source: String::new(),
+                        mint,
+                    }),
+                );
             }
 
-            TokenInstruction::Burn { amount: amt } | TokenInstruction::BurnChecked { amount: amt, .. } => {
-                let mint = &instruction.accounts()[1];
-                if mint.to_string().as_str() != spl_token_address {
+            TokenInstruction::FreezeAccount => {
+                let mint = instruction_account(instruction, 1)?.to_string();
+                if !tracked_mints.contains(&mint) {
+                    return Ok(());
+                }
+
+                output.add(
+                    token_program,
+                    Item::Freeze(Freeze {
+                        account: instruction_account(instruction, 0)?.to_string(),
+                        freeze_authority: instruction_account(instruction, 2)?.to_string(),
+                        owner: String::new(),
+                        owner_source: String::new(),
+                        mint,
+                    }),
+                );
+            }
+            TokenInstruction::ThawAccount => {
+                let mint = instruction_account(instruction, 1)?.to_string();
+                if !tracked_mints.contains(&mint) {
                     return Ok(());
                 }
 
-                let account_from = &instruction.accounts()[0];
-                output.add(Item::Burn(Burn {
-                    from: account_from.to_string(),
-                    amount: amount_to_decimals(amt as f64, spl_token_decimal as f64),
-                    from_owner: String::new(),
-                }));
+                output.add(
+                    token_program,
+                    Item::Thaw(Thaw {
+                        account: instruction_account(instruction, 0)?.to_string(),
+                        freeze_authority: instruction_account(instruction, 2)?.to_string(),
+                        owner: String::new(),
+                        owner_source: String::new(),
+                        mint,
+                    }),
+                );
             }
+
             TokenInstruction::InitializeAccount {} => {
-                let mint = &instruction.accounts()[1];
-                if mint.to_string().as_str() != spl_token_address {
+                let mint = &instruction_account(instruction, 1)?;
+                if !tracked_mints.contains(&mint.to_string()) {
                     return Ok(());
                 }
 
-                let account = &instruction.accounts()[0];
-                let owner = &instruction.accounts()[2];
+                let account = &instruction_account(instruction, 0)?;
+                let owner = &instruction_account(instruction, 2)?;
 
-                output.add(Item::InitializedAccount(InitializedAccount {
-                    account: account.to_string(),
-                    mint: mint.to_string(),
-                    owner: owner.to_string(),
-                }));
+                output.add(
+                    token_program,
+                    Item::InitializedAccount(InitializedAccount {
+                        account: account.to_string(),
+                        mint: mint.to_string(),
+                        owner: owner.to_string(),
+                    }),
+                );
             }
             TokenInstruction::InitializeAccount2 { owner: ow } | TokenInstruction::InitializeAccount3 { owner: ow } => {
-                let mint = &instruction.accounts()[1];
-                if mint.to_string().as_str() != spl_token_address {
+                let mint = &instruction_account(instruction, 1)?;
+                if !tracked_mints.contains(&mint.to_string()) {
                     return Ok(());
                 }
 
-                let account = &instruction.accounts()[0];
+                let account = &instruction_account(instruction, 0)?;
 
-                output.add(Item::InitializedAccount(InitializedAccount {
-                    account: account.to_string(),
-                    mint: mint.to_string(),
-                    owner: bs58::encode(ow).into_string(),
-                }));
+                output.add(
+                    token_program,
+                    Item::InitializedAccount(InitializedAccount {
+                        account: account.to_string(),
+                        mint: mint.to_string(),
+                        owner: bs58::encode(ow).into_string(),
+                    }),
+                );
             }
             _ => {}
         },
@@ -358,16 +1157,447 @@ fn process_token_instruction(
     Ok(())
 }
 
-fn amount_to_decimals(amount: f64, decimal: f64) -> f64 {
-    let base: f64 = 10.0;
-    amount.div(&(base.powf(decimal)))
+/// Positions of the destination and authority accounts of a transfer instruction, the source
+/// account always coming first.
+struct TransferAccounts {
+    destination: usize,
+    authority: usize,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_transfer(
+    output: &mut OutputInstructions,
+    token_program: &str,
+    tracked_mints: &TrackedMints,
+    mint_decimals: &mut MintDecimals,
+    instruction: &InstructionView,
+    mint: String,
+    accounts: TransferAccounts,
+    amount: u64,
+    fee: u64,
+    checked_decimals: Option<u8>,
+) -> Result<(), Error> {
+    if !tracked_mints.contains(&mint) {
+        return Ok(());
+    }
+
+    let source = &instruction_account(instruction, 0)?;
+    let authority = &instruction_account(instruction, accounts.authority)?;
+    let decimals = mint_decimals.resolve(&mint, checked_decimals, instruction, source);
+    let net_amount = amount.saturating_sub(fee);
+    output.add(
+        token_program,
+        Item::Transfer(Transfer {
+            from: source.to_string(),
+            to: instruction_account(instruction, accounts.destination)?.to_string(),
+            amount: amount_to_decimals(amount as f64, decimals as f64),
+            ui_amount: 0.,
+            raw_amount: amount.to_string(),
+            decimals,
+            fee: amount_to_decimals(fee as f64, decimals as f64),
+            raw_fee: fee.to_string(),
+            net_amount: amount_to_decimals(net_amount as f64, decimals as f64),
+            raw_net_amount: net_amount.to_string(),
+            from_owner: String::new(),
+            to_owner: String::new(),
+            from_owner_source: String::new(),
+            to_owner_source: String::new(),
+            authority: authority.to_string(),
+            authority_kind: transfer_authority_kind(instruction, source, accounts.authority).to_string(),
+            mint,
+        }),
+    );
+
+    Ok(())
+}
+
+/// Tells how a transfer was authorized. A multisig authority does not sign the transaction itself but
+/// is immediately followed by its signer accounts, so only the account right after the authority is
+/// checked and the transfer hook accounts of Token-2022 coming after the signers are ignored.
+/// Otherwise the authority is either the source account's owner, as reported in the transaction's
+/// token balances, or its delegate.
+///
+/// Only transaction signers are seen: a program-derived authority followed by a transfer hook
+/// account signing the transaction is taken for a multisig, and a multisig whose signers are
+/// program-derived accounts for an owner or delegate.
+fn transfer_authority_kind(instruction: &InstructionView, source: &Address, authority_position: usize) -> &'static str {
+    let accounts = instruction.accounts();
+    let Some(authority) = accounts.get(authority_position) else {
+        return "owner";
+    };
+
+    if !is_transaction_signer(instruction, authority)
+        && accounts
+            .get(authority_position + 1)
+            .is_some_and(|account| is_transaction_signer(instruction, account))
+    {
+        return "multisig";
+    }
+
+    match token_balance(instruction, source) {
+        Some(balance) if !balance.owner.is_empty() && balance.owner != authority.to_string() => "delegate",
+        _ => "owner",
+    }
+}
+
+/// Tells whether `account` signed the instruction's transaction.
+fn is_transaction_signer(instruction: &InstructionView, account: &Address) -> bool {
+    let Some(message) = instruction
+        .confirmed_transaction()
+        .transaction
+        .as_ref()
+        .and_then(|trx| trx.message.as_ref())
+    else {
+        return false;
+    };
+    let num_signers = message
+        .header
+        .as_ref()
+        .map(|header| header.num_required_signatures as usize)
+        .unwrap_or(0);
+
+    message
+        .account_keys
+        .iter()
+        .take(num_signers)
+        .any(|key| key == account.0)
+}
+
+/// Adds the withdrawal of withheld transfer fees to the destination account. The withdrawn amount is
+/// not part of the instruction, it is set by `set_withheld_withdrawal_amounts` once the whole
+/// transaction is decoded.
+fn add_withdraw_withheld_tokens(
+    output: &mut OutputInstructions,
+    token_program: &str,
+    tracked_mints: &TrackedMints,
+    mint_decimals: &mut MintDecimals,
+    instruction: &InstructionView,
+    source_accounts: Vec<String>,
+) -> Result<(), Error> {
+    let mint = instruction_account(instruction, 0)?.to_string();
+    if !tracked_mints.contains(&mint) {
+        return Ok(());
+    }
+
+    let destination = &instruction_account(instruction, 1)?;
+    let decimals = mint_decimals.resolve(&mint, None, instruction, destination);
+    output.add(
+        token_program,
+        Item::WithdrawWithheldTokens(WithdrawWithheldTokens {
+            destination: destination.to_string(),
+            withdraw_authority: instruction_account(instruction, 2)?.to_string(),
+            source_accounts,
+            amount: 0.,
+            ui_amount: 0.,
+            raw_amount: "0".to_string(),
+            decimals,
+            destination_owner: String::new(),
+            destination_owner_source: String::new(),
+            mint,
+        }),
+    );
+
+    Ok(())
+}
+
+/// Sets the amount of the transaction's withheld fee withdrawals: the destination's balance change
+/// over the transaction, from its token balances, less the balance changes of the transaction's other
+/// decoded instructions. A transaction withdrawing to the same destination several times has the whole
+/// amount reported on its first withdrawal.
+fn set_withheld_withdrawal_amounts(trx: &ConfirmedTransaction, instructions: &mut [Instruction]) {
+    let mut destinations = HashSet::new();
+
+    for position in 0..instructions.len() {
+        let Some(Item::WithdrawWithheldTokens(withdraw)) = &instructions[position].item else {
+            continue;
+        };
+        if !destinations.insert(withdraw.destination.clone()) {
+            continue;
+        }
+
+        let mut amount = transaction_token_balance_change(trx, &withdraw.destination);
+        for other in instructions.iter() {
+            match &other.item {
+                Some(Item::WithdrawWithheldTokens(_)) | None => {}
+                Some(item) => {
+                    for delta in balance_deltas(item) {
+                        if delta.account == withdraw.destination && delta.mint == withdraw.mint {
+                            amount = amount - delta.amount;
+                        }
+                    }
+                }
+            }
+        }
+        let amount = if amount < BigInt::zero() { 0 } else { amount.to_u64() };
+
+        let Some(Item::WithdrawWithheldTokens(withdraw)) = &mut instructions[position].item else {
+            continue;
+        };
+        withdraw.amount = amount_to_decimals(amount as f64, withdraw.decimals as f64);
+        withdraw.raw_amount = amount.to_string();
+    }
 }
 
-pub fn is_token_transfer(spl_token_address: &str, pre_token_balances: &Vec<TokenBalance>, account: &Address) -> bool {
-    for token_balance in pre_token_balances.iter() {
-        if token_balance.owner.eq(account.to_string().as_str()) && token_balance.mint.eq(spl_token_address) {
-            return true;
+fn add_confidential_transfer(
+    output: &mut OutputInstructions,
+    token_program: &str,
+    tracked_mints: &TrackedMints,
+    mint_decimals: &mut MintDecimals,
+    instruction: &InstructionView,
+    confidential_instruction: ConfidentialTransferInstruction,
+) -> Result<(), Error> {
+    let account = &instruction_account(instruction, 0)?;
+    let mint = match confidential_instruction {
+        ConfidentialTransferInstruction::ApplyPendingBalance => {
+            let Some(mint) = token_account_mint(instruction, account) else {
+                return Ok(());
+            };
+            mint
+        }
+        _ => instruction_account(instruction, 1)?.to_string(),
+    };
+    if !tracked_mints.contains(&mint) {
+        return Ok(());
+    }
+
+    let item = match confidential_instruction {
+        ConfidentialTransferInstruction::Deposit { amount, decimals } => {
+            let decimals = mint_decimals.resolve(&mint, Some(decimals), instruction, account);
+            Item::ConfidentialDeposit(ConfidentialDeposit {
+                account: account.to_string(),
+                owner: String::new(),
+                amount: amount_to_decimals(amount as f64, decimals as f64),
+                ui_amount: 0.,
+                raw_amount: amount.to_string(),
+                decimals,
+                owner_source: String::new(),
+                mint,
+            })
         }
+        ConfidentialTransferInstruction::Withdraw { amount, decimals } => {
+            let decimals = mint_decimals.resolve(&mint, Some(decimals), instruction, account);
+            Item::ConfidentialWithdraw(ConfidentialWithdraw {
+                account: account.to_string(),
+                owner: String::new(),
+                amount: amount_to_decimals(amount as f64, decimals as f64),
+                ui_amount: 0.,
+                raw_amount: amount.to_string(),
+                decimals,
+                owner_source: String::new(),
+                mint,
+            })
+        }
+        ConfidentialTransferInstruction::Transfer => Item::ConfidentialTransfer(ConfidentialTransfer {
+            from: account.to_string(),
+            to: instruction_account(instruction, 2)?.to_string(),
+            from_owner: String::new(),
+            to_owner: String::new(),
+            from_owner_source: String::new(),
+            to_owner_source: String::new(),
+            mint,
+        }),
+        ConfidentialTransferInstruction::ApplyPendingBalance => Item::ApplyPendingBalance(ApplyPendingBalance {
+            account: account.to_string(),
+            owner: String::new(),
+            owner_source: String::new(),
+            mint,
+        }),
+    };
+
+    output.add(token_program, item);
+
+    Ok(())
+}
+
+fn add_mint_to(
+    output: &mut OutputInstructions,
+    token_program: &str,
+    tracked_mints: &TrackedMints,
+    mint_decimals: &mut MintDecimals,
+    instruction: &InstructionView,
+    amount: u64,
+    checked_decimals: Option<u8>,
+) -> Result<(), Error> {
+    let mint = instruction_account(instruction, 0)?.to_string();
+    if !tracked_mints.contains(&mint) {
+        return Ok(());
+    }
+
+    let account_to = &instruction_account(instruction, 1)?;
+    let decimals = mint_decimals.resolve(&mint, checked_decimals, instruction, account_to);
+    output.add(
+        token_program,
+        Item::Mint(Mint {
+            to: account_to.to_string(),
+            amount: amount_to_decimals(amount as f64, decimals as f64),
+            ui_amount: 0.,
+            raw_amount: amount.to_string(),
+            decimals,
+            to_owner: String::new(),
+            to_owner_source: String::new(),
+            mint,
+        }),
+    );
+
+    Ok(())
+}
+
+fn add_burn(
+    output: &mut OutputInstructions,
+    token_program: &str,
+    tracked_mints: &TrackedMints,
+    mint_decimals: &mut MintDecimals,
+    instruction: &InstructionView,
+    amount: u64,
+    checked_decimals: Option<u8>,
+) -> Result<(), Error> {
+    let mint = instruction_account(instruction, 1)?.to_string();
+    if !tracked_mints.contains(&mint) {
+        return Ok(());
+    }
+
+    let account_from = &instruction_account(instruction, 0)?;
+    let decimals = mint_decimals.resolve(&mint, checked_decimals, instruction, account_from);
+    output.add(
+        token_program,
+        Item::Burn(Burn {
+            from: account_from.to_string(),
+            amount: amount_to_decimals(amount as f64, decimals as f64),
+            ui_amount: 0.,
+            raw_amount: amount.to_string(),
+            decimals,
+            from_owner: String::new(),
+            from_owner_source: String::new(),
+            mint,
+        }),
+    );
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_approve(
+    output: &mut OutputInstructions,
+    token_program: &str,
+    tracked_mints: &TrackedMints,
+    mint_decimals: &mut MintDecimals,
+    instruction: &InstructionView,
+    mint: String,
+    delegate: &Address,
+    amount: u64,
+    checked_decimals: Option<u8>,
+) -> Result<(), Error> {
+    if !tracked_mints.contains(&mint) {
+        return Ok(());
     }
-    false
+
+    let source = &instruction_account(instruction, 0)?;
+    let decimals = mint_decimals.resolve(&mint, checked_decimals, instruction, source);
+    output.add(
+        token_program,
+        Item::Approve(Approve {
+            source: source.to_string(),
+            delegate: delegate.to_string(),
+            owner: String::new(),
+            owner_source: String::new(),
+            amount: amount_to_decimals(amount as f64, decimals as f64),
+            ui_amount: 0.,
+            raw_amount: amount.to_string(),
+            decimals,
+            mint,
+        }),
+    );
+
+    Ok(())
+}
+
+pub(crate) const AUTHORITY_TYPE_ACCOUNT_OWNER: &str = "account_owner";
+
+fn authority_type_name(authority_type: &AuthorityType) -> &'static str {
+    match authority_type {
+        AuthorityType::MintTokens => "mint_tokens",
+        AuthorityType::FreezeAccount => "freeze_account",
+        AuthorityType::AccountOwner => AUTHORITY_TYPE_ACCOUNT_OWNER,
+        AuthorityType::CloseAccount => "close_account",
+        AuthorityType::TransferFeeConfig => "transfer_fee_config",
+        AuthorityType::WithheldWithdraw => "withheld_withdraw",
+        AuthorityType::CloseMint => "close_mint",
+        AuthorityType::InterestRate => "interest_rate",
+        AuthorityType::PermanentDelegate => "permanent_delegate",
+        AuthorityType::ConfidentialTransferMint => "confidential_transfer_mint",
+        AuthorityType::TransferHookProgramId => "transfer_hook_program_id",
+        AuthorityType::ConfidentialTransferFeeConfig => "confidential_transfer_fee_config",
+        AuthorityType::MetadataPointer => "metadata_pointer",
+    }
+}
+
+/// Returns the token balance of `account` reported in the transaction's pre or post token balances.
+pub(crate) fn token_balance<'a>(instruction: &InstructionView<'a>, account: &Address) -> Option<&'a TokenBalance> {
+    let trx = instruction.confirmed_transaction();
+    let meta = instruction.meta();
+
+    meta.pre_token_balances
+        .iter()
+        .chain(meta.post_token_balances.iter())
+        .find(|balance| trx.account_at(balance.account_index as u8) == *account)
+}
+
+/// Returns the change of the balance of the token `account` over the transaction, according to its
+/// pre and post token balances.
+fn transaction_token_balance_change(trx: &ConfirmedTransaction, account: &str) -> BigInt {
+    let Some(meta) = &trx.meta else {
+        return BigInt::zero();
+    };
+    let amount = |balances: &[TokenBalance]| {
+        balances
+            .iter()
+            .find(|balance| trx.account_at(balance.account_index as u8).to_string() == account)
+            .and_then(|balance| balance.ui_token_amount.as_ref())
+            .map(|ui_amount| parse_raw_amount(&ui_amount.amount))
+            .unwrap_or_default()
+    };
+
+    amount(&meta.post_token_balances) - amount(&meta.pre_token_balances)
+}
+
+/// Returns the balance of the token `account` at the start of the transaction, 0 when it did not exist.
+fn token_pre_balance(instruction: &InstructionView, account: &Address) -> u64 {
+    token_balance_amount(instruction, &instruction.meta().pre_token_balances, account)
+}
+
+fn token_balance_amount(instruction: &InstructionView, balances: &[TokenBalance], account: &Address) -> u64 {
+    let trx = instruction.confirmed_transaction();
+    balances
+        .iter()
+        .find(|balance| trx.account_at(balance.account_index as u8) == *account)
+        .and_then(|balance| balance.ui_token_amount.as_ref())
+        .and_then(|ui_amount| ui_amount.amount.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Returns the account at `position` in the instruction's accounts. Failed transactions may hold token
+/// instructions with fewer accounts than expected, the program having rejected them for it.
+fn instruction_account<'a>(instruction: &InstructionView<'a>, position: usize) -> Result<Address<'a>, Error> {
+    instruction
+        .accounts()
+        .into_iter()
+        .nth(position)
+        .ok_or_else(|| anyhow::anyhow!("missing account {} of token instruction", position))
+}
+
+/// Returns the mint of the token `account`, for instructions which do not reference the mint.
+fn token_account_mint(instruction: &InstructionView, account: &Address) -> Option<String> {
+    token_balance(instruction, account).map(|balance| balance.mint.clone())
+}
+
+/// Parses a `raw_amount` field, always the decimal representation of an on-chain `u64`.
+pub(crate) fn parse_raw_amount(raw_amount: &str) -> BigInt {
+    raw_amount
+        .parse::<BigInt>()
+        .unwrap_or_else(|_| panic!("invalid raw amount {}", raw_amount))
+}
+
+fn amount_to_decimals(amount: f64, decimal: f64) -> f64 {
+    let base: f64 = 10.0;
+    amount.div(&(base.powf(decimal)))
 }
@@ -0,0 +1,188 @@
+use crate::pb::sf::solana::spl::v1::r#type::{MintConfig, MintConfigs};
+use crate::{is_token_program, transactions_owned};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+use substreams::store::{StoreGet, StoreGetInt64, StoreGetString, StoreNew, StoreSet, StoreSetString};
+use substreams_solana::pb::sf::solana::r#type::v1::Block;
+use substreams_solana_program_instructions::option::COption;
+use substreams_solana_program_instructions::pubkey::Pubkey;
+use substreams_solana_program_instructions::token_instruction_2022::{AuthorityType, TokenInstruction};
+
+const FIELD_TOKEN_PROGRAM: &str = "token_program";
+const FIELD_DECIMALS: &str = "decimals";
+const FIELD_MINT_AUTHORITY: &str = "mint_authority";
+const FIELD_FREEZE_AUTHORITY: &str = "freeze_authority";
+const FIELD_EXTENSIONS: &str = "extensions";
+
+/// Token-2022 mint extensions, keyed by the tag of the instruction initializing them and, for extensions
+/// with their own instruction set, the sub-instruction tag of their `Initialize` instruction.
+const MINT_EXTENSIONS: &[(u8, Option<u8>, &str)] = &[
+    (25, None, "mint_close_authority"),
+    (26, Some(0), "transfer_fee_config"),
+    (27, Some(0), "confidential_transfer_mint"),
+    (28, Some(0), "default_account_state"),
+    (32, None, "non_transferable"),
+    (33, Some(0), "interest_bearing_config"),
+    (35, None, "permanent_delegate"),
+    (36, Some(0), "transfer_hook"),
+    (37, Some(0), "confidential_transfer_fee_config"),
+    (39, Some(0), "metadata_pointer"),
+    (40, Some(0), "group_pointer"),
+    (41, Some(0), "group_member_pointer"),
+    (43, Some(0), "scaled_ui_amount"),
+    (44, Some(0), "pausable"),
+];
+
+/// A change of one field of a mint's configuration.
+struct MintUpdate {
+    mint: String,
+    field: &'static str,
+    value: String,
+}
+
+/// Returns the mint configuration changes of the block, in order. Token-2022 extensions are initialized
+/// before `InitializeMint*`, so they are collected per mint and recorded along with it.
+fn mint_updates(block: Block) -> Vec<MintUpdate> {
+    let mut updates = vec![];
+    let mut extensions: BTreeMap<String, BTreeSet<&'static str>> = BTreeMap::new();
+
+    for (_, confirmed_trx) in transactions_owned(block, false) {
+        for instruction in confirmed_trx.walk_instructions() {
+            let program_id = instruction.program_id().to_string();
+            if !is_token_program(&program_id) {
+                continue;
+            }
+
+            let Some(mint) = instruction.accounts().first().map(|account| account.to_string()) else {
+                continue;
+            };
+
+            if let Some(extension) = mint_extension(instruction.data()) {
+                extensions.entry(mint).or_default().insert(extension);
+                continue;
+            }
+
+            let Ok(token_instruction) = TokenInstruction::unpack(instruction.data()) else {
+                continue;
+            };
+
+            let mut update = |field: &'static str, value: String| {
+                updates.push(MintUpdate {
+                    mint: mint.clone(),
+                    field,
+                    value,
+                });
+            };
+
+            match token_instruction {
+                TokenInstruction::InitializeMint {
+                    decimals,
+                    mint_authority,
+                    freeze_authority,
+                }
+                | TokenInstruction::InitializeMint2 {
+                    decimals,
+                    mint_authority,
+                    freeze_authority,
+                } => {
+                    let mint_extensions = extensions.remove(&mint).unwrap_or_default();
+                    update(FIELD_TOKEN_PROGRAM, program_id);
+                    update(FIELD_DECIMALS, decimals.to_string());
+                    update(FIELD_MINT_AUTHORITY, bs58::encode(mint_authority).into_string());
+                    update(FIELD_FREEZE_AUTHORITY, optional_authority(freeze_authority));
+                    update(
+                        FIELD_EXTENSIONS,
+                        mint_extensions.into_iter().collect::<Vec<_>>().join(","),
+                    );
+                }
+                TokenInstruction::SetAuthority {
+                    authority_type: AuthorityType::MintTokens,
+                    new_authority,
+                } => update(FIELD_MINT_AUTHORITY, optional_authority(new_authority)),
+                TokenInstruction::SetAuthority {
+                    authority_type: AuthorityType::FreezeAccount,
+                    new_authority,
+                } => update(FIELD_FREEZE_AUTHORITY, optional_authority(new_authority)),
+                _ => {}
+            }
+        }
+    }
+
+    updates
+}
+
+/// Returns the extension initialized by a Token-2022 extension initialization instruction.
+fn mint_extension(data: &[u8]) -> Option<&'static str> {
+    let tag = *data.first()?;
+    let sub_tag = data.get(1).copied();
+
+    MINT_EXTENSIONS
+        .iter()
+        .find(|(extension_tag, extension_sub_tag, _)| {
+            *extension_tag == tag && (extension_sub_tag.is_none() || *extension_sub_tag == sub_tag)
+        })
+        .map(|(_, _, extension)| *extension)
+}
+
+fn optional_authority(authority: COption<Pubkey>) -> String {
+    match authority {
+        COption::Some(authority) => bs58::encode(authority).into_string(),
+        COption::None => String::new(),
+    }
+}
+
+fn field_key(field: &str, mint: &str) -> String {
+    format!("{}:{}", field, mint)
+}
+
+/// Records the configuration of each mint, keyed by `<field>:<mint>`: the token program, decimals,
+/// mint and freeze authorities (kept up to date through `SetAuthority`) and the comma separated
+/// Token-2022 extensions initialized along with the mint.
+#[substreams::handlers::store]
+fn store_mint_registry(block: Block, store: StoreSetString) {
+    for (ordinal, update) in mint_updates(block).into_iter().enumerate() {
+        store.set(ordinal as u64, field_key(update.field, &update.mint), &update.value);
+    }
+}
+
+/// Emits the configuration at the end of the block of each mint initialized or whose authorities
+/// changed in the block. Mints initialized before `store_mint_registry`'s initial block only have
+/// their changed authorities set, along with their decimals when `store_mint_decimals` knows them.
+#[substreams::handlers::map]
+fn map_mint_registry(
+    clock: Clock,
+    block: Block,
+    store: StoreGetString,
+    decimals_store: StoreGetInt64,
+) -> Result<MintConfigs, Error> {
+    let mut seen = HashSet::new();
+    let mut mint_configs = vec![];
+
+    for update in mint_updates(block) {
+        if !seen.insert(update.mint.clone()) {
+            continue;
+        }
+
+        let field = |field: &str| store.get_last(field_key(field, &update.mint)).unwrap_or_default();
+        mint_configs.push(MintConfig {
+            slot: clock.number,
+            block_time: clock.timestamp,
+            token_program: field(FIELD_TOKEN_PROGRAM),
+            decimals: field(FIELD_DECIMALS)
+                .parse()
+                .ok()
+                .or_else(|| decimals_store.get_last(&update.mint).map(|decimals| decimals as u32)),
+            mint_authority: field(FIELD_MINT_AUTHORITY),
+            freeze_authority: field(FIELD_FREEZE_AUTHORITY),
+            extensions: field(FIELD_EXTENSIONS)
+                .split(',')
+                .filter(|extension| !extension.is_empty())
+                .map(|extension| extension.to_string())
+                .collect(),
+            mint: update.mint,
+        });
+    }
+
+    Ok(MintConfigs { mint_configs })
+}
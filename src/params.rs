@@ -0,0 +1,415 @@
+use crate::pb::sf::solana::spl::v1::r#type::{SplParams, TrackedMint};
+use anyhow::{anyhow, bail, Context};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+
+/// Parses the params once for the modules scoped to the tracked mints: the `index_spl_token` block
+/// index, `map_spl_instructions` and the stores it reads.
+#[substreams::handlers::map]
+fn map_params(params: String, _clock: Clock) -> Result<SplParams, Error> {
+    Ok(params.parse::<Params>()?.into())
+}
+
+/// Mints for which `map_spl_instructions` extracts instructions.
+pub enum TrackedMints {
+    /// Every mint is tracked, decimals are resolved from the transaction itself.
+    All,
+    /// Only the listed mints are tracked, keyed by mint address with their configured decimals, if any.
+    Only(HashMap<String, Option<u32>>),
+}
+
+impl TrackedMints {
+    pub fn contains(&self, mint: &str) -> bool {
+        match self {
+            TrackedMints::All => true,
+            TrackedMints::Only(mints) => mints.contains_key(mint),
+        }
+    }
+
+    /// Returns the tracked mints in address order, always empty when tracking all mints.
+    pub fn addresses(&self) -> Vec<String> {
+        match self {
+            TrackedMints::All => vec![],
+            TrackedMints::Only(mints) => {
+                let mut addresses: Vec<String> = mints.keys().cloned().collect();
+                addresses.sort();
+                addresses
+            }
+        }
+    }
+
+    /// Returns the decimals configured for `mint`, always `None` when tracking all mints.
+    pub fn decimals(&self, mint: &str) -> Option<u32> {
+        match self {
+            TrackedMints::All => None,
+            TrackedMints::Only(mints) => mints.get(mint).copied().flatten(),
+        }
+    }
+}
+
+/// Parameters of `map_params`.
+pub struct Params {
+    pub tracked_mints: TrackedMints,
+    /// When set, a token instruction that cannot be decoded halts the stream instead of being
+    /// reported in `SplInstructions.decode_errors`. Only applies to transactions touching a tracked mint.
+    pub strict: bool,
+    /// When set, instructions of failed transactions are extracted too, flagged through
+    /// `Instruction.succeeded` and `Instruction.error`.
+    pub include_failed_transactions: bool,
+}
+
+const SPL_TOKEN_ADDRESSES: &str = "spl_token_addresses";
+const SPL_TOKEN_ADDRESS: &str = "spl_token_address";
+const SPL_TOKEN_DECIMAL: &str = "spl_token_decimal";
+const STRICT: &str = "strict";
+const INCLUDE_FAILED_TRANSACTIONS: &str = "include_failed_transactions";
+
+impl FromStr for Params {
+    type Err = Error;
+
+    /// Parses the `map_params` params, a list of `key=value` pairs either `|` separated or in
+    /// URL query form (`&` separated, percent-encoded values).
+    ///
+    /// Mints are configured either through `spl_token_addresses=<mint>:<decimals>,<mint>:<decimals>`,
+    /// `spl_token_addresses=*` to track every mint, or the single mint form
+    /// `spl_token_address=<mint>|spl_token_decimal=<decimals>`. Both forms can be combined. The
+    /// `:<decimals>` suffix is optional, configured decimals are only used when the chain does not
+    /// tell the mint's decimals.
+    ///
+    /// `strict=true` restores the historical behavior of panicking on undecodable token instructions and
+    /// `include_failed_transactions=true` extracts instructions of failed transactions as well.
+    ///
+    /// Unknown or repeated keys, malformed mint addresses, invalid values and params tracking no mint
+    /// are rejected.
+    fn from_str(params: &str) -> Result<Self, Self::Err> {
+        let mut all = false;
+        let mut strict = false;
+        let mut include_failed_transactions = false;
+        let mut mints = HashMap::new();
+
+        let mut spl_token_address = None;
+        let mut spl_token_decimal = None;
+
+        let mut seen = HashSet::new();
+        for (key, value) in key_values(params)? {
+            if !seen.insert(key.clone()) {
+                bail!("param {:?} is set more than once", key);
+            }
+
+            match key.as_str() {
+                SPL_TOKEN_ADDRESS => spl_token_address = Some(parse_mint(&value)?),
+                SPL_TOKEN_DECIMAL => spl_token_decimal = Some(parse_decimals(&value)?),
+                STRICT => strict = parse_bool(STRICT, &value)?,
+                INCLUDE_FAILED_TRANSACTIONS => {
+                    include_failed_transactions = parse_bool(INCLUDE_FAILED_TRANSACTIONS, &value)?
+                }
+                SPL_TOKEN_ADDRESSES => {
+                    for entry in value.split(',') {
+                        if entry == "*" {
+                            all = true;
+                            continue;
+                        }
+
+                        let (mint, decimals) = match entry.split_once(':') {
+                            Some((mint, decimals)) => (parse_mint(mint)?, Some(parse_decimals(decimals)?)),
+                            None => (parse_mint(entry)?, None),
+                        };
+                        mints.insert(mint, decimals);
+                    }
+                }
+                _ => bail!(
+                    "unknown param {:?}, expected one of {}, {}, {}, {} or {}",
+                    key,
+                    SPL_TOKEN_ADDRESSES,
+                    SPL_TOKEN_ADDRESS,
+                    SPL_TOKEN_DECIMAL,
+                    STRICT,
+                    INCLUDE_FAILED_TRANSACTIONS
+                ),
+            }
+        }
+
+        match spl_token_address {
+            Some(mint) => {
+                mints.insert(mint, spl_token_decimal);
+            }
+            None if spl_token_decimal.is_some() => {
+                bail!("param {} requires {} to be set", SPL_TOKEN_DECIMAL, SPL_TOKEN_ADDRESS)
+            }
+            None => {}
+        }
+
+        if !all && mints.is_empty() {
+            bail!(
+                "no mint to track, set {}=<mint>:<decimals>,... or {}=* to track every mint",
+                SPL_TOKEN_ADDRESSES,
+                SPL_TOKEN_ADDRESSES
+            );
+        }
+
+        Ok(Params {
+            tracked_mints: if all {
+                TrackedMints::All
+            } else {
+                TrackedMints::Only(mints)
+            },
+            strict,
+            include_failed_transactions,
+        })
+    }
+}
+
+impl From<Params> for SplParams {
+    fn from(params: Params) -> Self {
+        let (all_mints, tracked_mints) = match params.tracked_mints {
+            TrackedMints::All => (true, vec![]),
+            TrackedMints::Only(mints) => {
+                let mut tracked_mints: Vec<TrackedMint> = mints
+                    .into_iter()
+                    .map(|(mint, decimals)| TrackedMint { mint, decimals })
+                    .collect();
+                tracked_mints.sort_by(|a, b| a.mint.cmp(&b.mint));
+                (false, tracked_mints)
+            }
+        };
+
+        SplParams {
+            all_mints,
+            tracked_mints,
+            strict: params.strict,
+            include_failed_transactions: params.include_failed_transactions,
+        }
+    }
+}
+
+impl From<SplParams> for Params {
+    fn from(params: SplParams) -> Self {
+        Params {
+            tracked_mints: if params.all_mints {
+                TrackedMints::All
+            } else {
+                TrackedMints::Only(
+                    params
+                        .tracked_mints
+                        .into_iter()
+                        .map(|tracked_mint| (tracked_mint.mint, tracked_mint.decimals))
+                        .collect(),
+                )
+            },
+            strict: params.strict,
+            include_failed_transactions: params.include_failed_transactions,
+        }
+    }
+}
+
+/// Splits the params into key and value pairs. Params without any `|` are in URL query form, which
+/// includes a single percent-encoded pair such as `spl_token_addresses=<mint>%3A9`.
+fn key_values(params: &str) -> Result<Vec<(String, String)>, Error> {
+    let params = params.trim();
+    if params.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let query_form = !params.contains('|');
+    params
+        .split(if query_form { '&' } else { '|' })
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("param {:?} is not of the form key=value", pair))?;
+
+            if query_form {
+                Ok((percent_decode(key)?, percent_decode(value)?))
+            } else {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Result<String, Error> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next(), input.next()];
+                let [Some(high), Some(low)] = hex else {
+                    bail!("truncated percent-encoding in param {:?}", value);
+                };
+                let decoded = std::str::from_utf8(&[high, low])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| anyhow!("invalid percent-encoding in param {:?}", value))?;
+                bytes.push(decoded);
+            }
+            _ => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).with_context(|| format!("param {:?} is not valid UTF-8", value))
+}
+
+/// Validates that `mint` is a base58 encoded 32 bytes address.
+fn parse_mint(mint: &str) -> Result<String, Error> {
+    let bytes = bs58::decode(mint)
+        .into_vec()
+        .map_err(|err| anyhow!("invalid mint address {:?}: {}", mint, err))?;
+    if bytes.len() != 32 {
+        bail!(
+            "invalid mint address {:?}: expected 32 bytes, got {}",
+            mint,
+            bytes.len()
+        );
+    }
+
+    Ok(mint.to_string())
+}
+
+fn parse_decimals(decimals: &str) -> Result<u32, Error> {
+    decimals
+        .parse::<u8>()
+        .map(|decimals| decimals as u32)
+        .with_context(|| format!("invalid decimals {:?}, expected an integer between 0 and 255", decimals))
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, Error> {
+    value
+        .parse()
+        .with_context(|| format!("invalid value {:?} for param {}, expected true or false", value, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const WSOL: &str = "So11111111111111111111111111111111111111112";
+
+    fn parse(params: &str) -> Params {
+        params.parse().unwrap()
+    }
+
+    fn parse_err(params: &str) -> String {
+        match params.parse::<Params>() {
+            Ok(_) => panic!("params {:?} should be rejected", params),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn pipe_form() {
+        let params = parse(&format!(
+            "spl_token_addresses={}:6,{}|strict=true|include_failed_transactions=true",
+            USDC, WSOL
+        ));
+
+        assert_eq!(
+            params.tracked_mints.addresses(),
+            vec![USDC.to_string(), WSOL.to_string()]
+        );
+        assert_eq!(params.tracked_mints.decimals(USDC), Some(6));
+        assert_eq!(params.tracked_mints.decimals(WSOL), None);
+        assert!(params.strict);
+        assert!(params.include_failed_transactions);
+    }
+
+    #[test]
+    fn single_mint_form() {
+        let params = parse(&format!("spl_token_address={}|spl_token_decimal=6", USDC));
+
+        assert_eq!(params.tracked_mints.addresses(), vec![USDC.to_string()]);
+        assert_eq!(params.tracked_mints.decimals(USDC), Some(6));
+        assert!(!params.strict);
+        assert!(!params.include_failed_transactions);
+    }
+
+    #[test]
+    fn all_mints() {
+        let params = parse("spl_token_addresses=*");
+
+        assert!(matches!(params.tracked_mints, TrackedMints::All));
+        assert!(params.tracked_mints.contains(USDC));
+        assert!(params.tracked_mints.addresses().is_empty());
+    }
+
+    #[test]
+    fn query_form() {
+        let params = parse(&format!("spl_token_addresses={}%3A6%2C{}&strict=true", USDC, WSOL));
+
+        assert_eq!(
+            params.tracked_mints.addresses(),
+            vec![USDC.to_string(), WSOL.to_string()]
+        );
+        assert_eq!(params.tracked_mints.decimals(USDC), Some(6));
+        assert!(params.strict);
+    }
+
+    #[test]
+    fn query_form_single_pair() {
+        let params = parse(&format!("spl_token_addresses={}%3A9", WSOL));
+
+        assert_eq!(params.tracked_mints.addresses(), vec![WSOL.to_string()]);
+        assert_eq!(params.tracked_mints.decimals(WSOL), Some(9));
+    }
+
+    #[test]
+    fn spl_params_round_trip() {
+        let spl_params: SplParams = parse(&format!("spl_token_addresses={}:6,{}|strict=true", USDC, WSOL)).into();
+        assert_eq!(spl_params.tracked_mints[0].mint, USDC);
+        assert_eq!(spl_params.tracked_mints[1].mint, WSOL);
+
+        let params = Params::from(spl_params);
+        assert_eq!(
+            params.tracked_mints.addresses(),
+            vec![USDC.to_string(), WSOL.to_string()]
+        );
+        assert_eq!(params.tracked_mints.decimals(USDC), Some(6));
+        assert_eq!(params.tracked_mints.decimals(WSOL), None);
+        assert!(params.strict);
+        assert!(!params.include_failed_transactions);
+
+        let params = Params::from(SplParams::from(parse("spl_token_addresses=*")));
+        assert!(matches!(params.tracked_mints, TrackedMints::All));
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert!(parse_err(&format!("spl_token_addresses={}|unknown=1", USDC)).contains("unknown param"));
+    }
+
+    #[test]
+    fn repeated_key() {
+        let err = parse_err(&format!("spl_token_addresses={}|spl_token_addresses={}", USDC, WSOL));
+        assert!(err.contains("more than once"));
+    }
+
+    #[test]
+    fn bad_base58() {
+        assert!(parse_err("spl_token_addresses=0OIl").contains("invalid mint address"));
+        assert!(parse_err("spl_token_addresses=abc").contains("expected 32 bytes"));
+    }
+
+    #[test]
+    fn bad_decimals() {
+        assert!(parse_err(&format!("spl_token_addresses={}:six", USDC)).contains("invalid decimals"));
+        assert!(parse_err(&format!("spl_token_addresses={}:256", USDC)).contains("invalid decimals"));
+        assert!(parse_err(&format!("spl_token_address={}|spl_token_decimal=-1", USDC)).contains("invalid decimals"));
+    }
+
+    #[test]
+    fn decimals_without_address() {
+        let err = parse_err(&format!("spl_token_addresses={}|spl_token_decimal=6", USDC));
+        assert!(err.contains("requires"));
+    }
+
+    #[test]
+    fn no_mint() {
+        assert!(parse_err("").contains("no mint to track"));
+        assert!(parse_err("strict=true").contains("no mint to track"));
+    }
+}
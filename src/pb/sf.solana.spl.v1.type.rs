@@ -5,6 +5,56 @@
 pub struct SplInstructions {
     #[prost(message, repeated, tag="1")]
     pub instructions: ::prost::alloc::vec::Vec<Instruction>,
+    #[prost(message, repeated, tag="2")]
+    pub decimals_mismatches: ::prost::alloc::vec::Vec<DecimalsMismatch>,
+    #[prost(message, repeated, tag="3")]
+    pub decode_errors: ::prost::alloc::vec::Vec<DecodeError>,
+    #[prost(message, optional, tag="4")]
+    pub owner_resolution_stats: ::core::option::Option<OwnerResolutionStats>,
+    #[prost(string, repeated, tag="5")]
+    pub unresolved_decimals: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(bool, tag="6")]
+    pub all_mints_tracked: bool,
+    #[prost(string, repeated, tag="7")]
+    pub tracked_mints: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct OwnerResolutionStats {
+    #[prost(uint32, tag="1")]
+    pub lookups: u32,
+    #[prost(uint32, tag="2")]
+    pub found: u32,
+    #[prost(uint32, tag="3")]
+    pub not_found: u32,
+    #[prost(uint32, tag="4")]
+    pub decode_failures: u32,
+    #[prost(uint32, tag="5")]
+    pub fallback_resolved: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DecimalsMismatch {
+    #[prost(string, tag="1")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(uint32, tag="2")]
+    pub configured_decimals: u32,
+    #[prost(uint32, tag="3")]
+    pub chain_decimals: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DecodeError {
+    #[prost(string, tag="1")]
+    pub transaction_hash: ::prost::alloc::string::String,
+    #[prost(uint32, tag="2")]
+    pub instruction_index: u32,
+    #[prost(uint32, optional, tag="3")]
+    pub inner_instruction_index: ::core::option::Option<u32>,
+    #[prost(bytes="vec", tag="4")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, tag="5")]
+    pub error: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -13,7 +63,29 @@ pub struct Instruction {
     pub instruction_id: ::prost::alloc::string::String,
     #[prost(string, tag="2")]
     pub transaction_hash: ::prost::alloc::string::String,
-    #[prost(oneof="instruction::Item", tags="10, 11, 12, 13")]
+    #[prost(string, tag="3")]
+    pub token_program: ::prost::alloc::string::String,
+    #[prost(uint64, tag="4")]
+    pub slot: u64,
+    #[prost(message, optional, tag="5")]
+    pub block_time: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(uint32, tag="6")]
+    pub transaction_index: u32,
+    #[prost(string, tag="7")]
+    pub fee_payer: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag="8")]
+    pub signers: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint32, tag="100")]
+    pub instruction_index: u32,
+    #[prost(uint32, optional, tag="101")]
+    pub inner_instruction_index: ::core::option::Option<u32>,
+    #[prost(uint32, tag="102")]
+    pub stack_height: u32,
+    #[prost(bool, tag="103")]
+    pub succeeded: bool,
+    #[prost(string, tag="104")]
+    pub error: ::prost::alloc::string::String,
+    #[prost(oneof="instruction::Item", tags="10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25")]
     pub item: ::core::option::Option<instruction::Item>,
 }
 /// Nested message and enum types in `Instruction`.
@@ -29,6 +101,30 @@ pub mod instruction {
         Transfer(super::Transfer),
         #[prost(message, tag="13")]
         InitializedAccount(super::InitializedAccount),
+        #[prost(message, tag="14")]
+        Approve(super::Approve),
+        #[prost(message, tag="15")]
+        Revoke(super::Revoke),
+        #[prost(message, tag="16")]
+        SetAuthority(super::SetAuthority),
+        #[prost(message, tag="17")]
+        CloseAccount(super::CloseAccount),
+        #[prost(message, tag="18")]
+        Freeze(super::Freeze),
+        #[prost(message, tag="19")]
+        Thaw(super::Thaw),
+        #[prost(message, tag="20")]
+        WithdrawWithheldTokens(super::WithdrawWithheldTokens),
+        #[prost(message, tag="21")]
+        HarvestWithheldTokensToMint(super::HarvestWithheldTokensToMint),
+        #[prost(message, tag="22")]
+        ConfidentialDeposit(super::ConfidentialDeposit),
+        #[prost(message, tag="23")]
+        ConfidentialWithdraw(super::ConfidentialWithdraw),
+        #[prost(message, tag="24")]
+        ConfidentialTransfer(super::ConfidentialTransfer),
+        #[prost(message, tag="25")]
+        ApplyPendingBalance(super::ApplyPendingBalance),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -44,6 +140,33 @@ pub struct Transfer {
     pub from_owner: ::prost::alloc::string::String,
     #[prost(string, tag="6")]
     pub to_owner: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="8")]
+    pub raw_amount: ::prost::alloc::string::String,
+    #[prost(uint32, tag="9")]
+    pub decimals: u32,
+    #[prost(string, tag="10")]
+    pub authority: ::prost::alloc::string::String,
+    /// `owner`, `delegate` or `multisig`, a multisig with program-derived signers being seen as `owner` or
+    /// `delegate`.
+    #[prost(string, tag="11")]
+    pub authority_kind: ::prost::alloc::string::String,
+    /// `foundational_store`, `owner_change`, `initialized_account` or `token_balance`, empty when unresolved.
+    #[prost(string, tag="12")]
+    pub from_owner_source: ::prost::alloc::string::String,
+    #[prost(string, tag="13")]
+    pub to_owner_source: ::prost::alloc::string::String,
+    #[prost(double, tag="14")]
+    pub fee: f64,
+    #[prost(string, tag="15")]
+    pub raw_fee: ::prost::alloc::string::String,
+    #[prost(double, tag="16")]
+    pub net_amount: f64,
+    #[prost(string, tag="17")]
+    pub raw_net_amount: ::prost::alloc::string::String,
+    #[prost(double, tag="18")]
+    pub ui_amount: f64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -54,6 +177,16 @@ pub struct Mint {
     pub amount: f64,
     #[prost(string, tag="4")]
     pub to_owner: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub raw_amount: ::prost::alloc::string::String,
+    #[prost(uint32, tag="7")]
+    pub decimals: u32,
+    #[prost(string, tag="8")]
+    pub to_owner_source: ::prost::alloc::string::String,
+    #[prost(double, tag="9")]
+    pub ui_amount: f64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -64,6 +197,16 @@ pub struct Burn {
     pub amount: f64,
     #[prost(string, tag="4")]
     pub from_owner: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub raw_amount: ::prost::alloc::string::String,
+    #[prost(uint32, tag="7")]
+    pub decimals: u32,
+    #[prost(string, tag="8")]
+    pub from_owner_source: ::prost::alloc::string::String,
+    #[prost(double, tag="9")]
+    pub ui_amount: f64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -75,4 +218,341 @@ pub struct InitializedAccount {
     #[prost(string, tag="3")]
     pub owner: ::prost::alloc::string::String,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Approve {
+    #[prost(string, tag="1")]
+    pub source: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub delegate: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(double, tag="4")]
+    pub amount: f64,
+    #[prost(string, tag="5")]
+    pub raw_amount: ::prost::alloc::string::String,
+    #[prost(uint32, tag="6")]
+    pub decimals: u32,
+    #[prost(string, tag="7")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="8")]
+    pub owner_source: ::prost::alloc::string::String,
+    #[prost(double, tag="9")]
+    pub ui_amount: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Revoke {
+    #[prost(string, tag="1")]
+    pub source: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub owner_source: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetAuthority {
+    #[prost(string, tag="1")]
+    pub target: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub authority_type: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub old_authority: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub new_authority: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub raw_account_pre_balance: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CloseAccount {
+    #[prost(string, tag="1")]
+    pub account: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub destination: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub owner_source: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Freeze {
+    #[prost(string, tag="1")]
+    pub account: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub freeze_authority: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub owner_source: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Thaw {
+    #[prost(string, tag="1")]
+    pub account: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub freeze_authority: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub owner_source: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WithdrawWithheldTokens {
+    #[prost(string, tag="1")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub destination: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub withdraw_authority: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag="4")]
+    pub source_accounts: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(double, tag="5")]
+    pub amount: f64,
+    #[prost(string, tag="6")]
+    pub raw_amount: ::prost::alloc::string::String,
+    #[prost(uint32, tag="7")]
+    pub decimals: u32,
+    #[prost(string, tag="8")]
+    pub destination_owner: ::prost::alloc::string::String,
+    #[prost(string, tag="9")]
+    pub destination_owner_source: ::prost::alloc::string::String,
+    #[prost(double, tag="10")]
+    pub ui_amount: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HarvestWithheldTokensToMint {
+    #[prost(string, tag="1")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag="2")]
+    pub source_accounts: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfidentialDeposit {
+    #[prost(string, tag="1")]
+    pub account: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(double, tag="4")]
+    pub amount: f64,
+    #[prost(string, tag="5")]
+    pub raw_amount: ::prost::alloc::string::String,
+    #[prost(uint32, tag="6")]
+    pub decimals: u32,
+    #[prost(string, tag="7")]
+    pub owner_source: ::prost::alloc::string::String,
+    #[prost(double, tag="8")]
+    pub ui_amount: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfidentialWithdraw {
+    #[prost(string, tag="1")]
+    pub account: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(double, tag="4")]
+    pub amount: f64,
+    #[prost(string, tag="5")]
+    pub raw_amount: ::prost::alloc::string::String,
+    #[prost(uint32, tag="6")]
+    pub decimals: u32,
+    #[prost(string, tag="7")]
+    pub owner_source: ::prost::alloc::string::String,
+    #[prost(double, tag="8")]
+    pub ui_amount: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfidentialTransfer {
+    #[prost(string, tag="1")]
+    pub from: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub to: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub from_owner: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub to_owner: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub from_owner_source: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub to_owner_source: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplyPendingBalance {
+    #[prost(string, tag="1")]
+    pub account: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub owner_source: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BalanceChanges {
+    #[prost(message, repeated, tag="1")]
+    pub balance_changes: ::prost::alloc::vec::Vec<BalanceChange>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BalanceChange {
+    /// `account` or `owner`.
+    #[prost(string, tag="1")]
+    pub holder_type: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub old_balance: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub new_balance: ::prost::alloc::string::String,
+    #[prost(bool, tag="6")]
+    pub confidential: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SupplySnapshots {
+    #[prost(message, repeated, tag="1")]
+    pub supply_snapshots: ::prost::alloc::vec::Vec<SupplySnapshot>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SupplySnapshot {
+    #[prost(string, tag="1")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(uint64, tag="2")]
+    pub slot: u64,
+    #[prost(message, optional, tag="3")]
+    pub block_time: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(string, tag="4")]
+    pub supply: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub previous_supply: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MintConfigs {
+    #[prost(message, repeated, tag="1")]
+    pub mint_configs: ::prost::alloc::vec::Vec<MintConfig>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MintConfig {
+    #[prost(string, tag="1")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(uint64, tag="2")]
+    pub slot: u64,
+    #[prost(message, optional, tag="3")]
+    pub block_time: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(string, tag="4")]
+    pub token_program: ::prost::alloc::string::String,
+    #[prost(uint32, optional, tag="5")]
+    pub decimals: ::core::option::Option<u32>,
+    #[prost(string, tag="6")]
+    pub mint_authority: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub freeze_authority: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag="8")]
+    pub extensions: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenMetadatas {
+    #[prost(message, repeated, tag="1")]
+    pub token_metadata: ::prost::alloc::vec::Vec<TokenMetadata>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenMetadata {
+    #[prost(string, tag="1")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(uint64, tag="2")]
+    pub slot: u64,
+    #[prost(message, optional, tag="3")]
+    pub block_time: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(string, tag="4")]
+    pub update_authority: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub uri: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag="8")]
+    pub additional_metadata: ::prost::alloc::vec::Vec<AdditionalMetadata>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AdditionalMetadata {
+    #[prost(string, tag="1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BalanceDiscrepancies {
+    #[prost(message, repeated, tag="1")]
+    pub balance_discrepancies: ::prost::alloc::vec::Vec<BalanceDiscrepancy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BalanceDiscrepancy {
+    #[prost(string, tag="1")]
+    pub transaction_hash: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub account: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub decoded_delta: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub chain_delta: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SplParams {
+    #[prost(bool, tag="1")]
+    pub all_mints: bool,
+    #[prost(message, repeated, tag="2")]
+    pub tracked_mints: ::prost::alloc::vec::Vec<TrackedMint>,
+    #[prost(bool, tag="3")]
+    pub strict: bool,
+    #[prost(bool, tag="4")]
+    pub include_failed_transactions: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackedMint {
+    #[prost(string, tag="1")]
+    pub mint: ::prost::alloc::string::String,
+    #[prost(uint32, optional, tag="2")]
+    pub decimals: ::core::option::Option<u32>,
+}
 // @@protoc_insertion_point(module)
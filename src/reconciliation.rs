@@ -0,0 +1,109 @@
+use crate::balances::balance_deltas;
+use crate::pb::sf::solana::spl::v1::r#type::{BalanceDiscrepancies, BalanceDiscrepancy, SplInstructions};
+use crate::{parse_raw_amount, transactions_owned};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use substreams::errors::Error;
+use substreams::scalar::BigInt;
+use substreams_solana::pb::sf::solana::r#type::v1::{Block, ConfirmedTransaction, TokenBalance};
+
+/// Net balance change per `(account, mint)` within a transaction.
+type Deltas = BTreeMap<(String, String), BigInt>;
+
+/// Compares, per transaction and token account, the balance change implied by the decoded instructions
+/// of `map_spl_instructions` (see `balance_deltas`) with the one reported by the transaction's pre and
+/// post token balances, emitting the accounts for which they differ. Only the mints tracked by
+/// `map_spl_instructions` are compared. A discrepancy usually points at an instruction type the
+/// decoder does not handle.
+#[substreams::handlers::map]
+fn map_balance_discrepancies(block: Block, spl_instructions: SplInstructions) -> Result<BalanceDiscrepancies, Error> {
+    let tracked_mints: HashSet<&String> = spl_instructions.tracked_mints.iter().collect();
+    let is_tracked = |mint: &String| spl_instructions.all_mints_tracked || tracked_mints.contains(mint);
+
+    let mut decoded: HashMap<String, Deltas> = HashMap::new();
+    for instruction in &spl_instructions.instructions {
+        if !instruction.succeeded {
+            continue;
+        }
+
+        let Some(item) = &instruction.item else {
+            continue;
+        };
+        let deltas = decoded.entry(instruction.transaction_hash.clone()).or_default();
+        for delta in balance_deltas(item) {
+            add_delta(deltas, delta.account, delta.mint, delta.amount);
+        }
+    }
+
+    let mut balance_discrepancies = vec![];
+    for (_, confirmed_trx) in transactions_owned(block, false) {
+        let hash = bs58::encode(confirmed_trx.hash()).into_string();
+        let mut chain = chain_deltas(&confirmed_trx);
+        chain.retain(|(_, mint), _| is_tracked(mint));
+
+        let decoded = decoded.remove(&hash).unwrap_or_default();
+        let mut keys: Vec<&(String, String)> = chain.keys().chain(decoded.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        for key in keys {
+            let chain_delta = chain.get(key).cloned().unwrap_or_default();
+            let decoded_delta = decoded.get(key).cloned().unwrap_or_default();
+            if chain_delta == decoded_delta {
+                continue;
+            }
+
+            balance_discrepancies.push(BalanceDiscrepancy {
+                transaction_hash: hash.clone(),
+                account: key.0.clone(),
+                mint: key.1.clone(),
+                decoded_delta: decoded_delta.to_string(),
+                chain_delta: chain_delta.to_string(),
+            });
+        }
+    }
+
+    Ok(BalanceDiscrepancies { balance_discrepancies })
+}
+
+fn add_delta(deltas: &mut Deltas, account: &str, mint: &str, amount: BigInt) {
+    let delta = deltas.entry((account.to_string(), mint.to_string())).or_default();
+    *delta = delta.clone() + amount;
+}
+
+/// Returns the balance changes reported by the transaction's token balances, an account missing
+/// from the pre (created) or post (closed) balances counting as a zero balance.
+fn chain_deltas(trx: &ConfirmedTransaction) -> Deltas {
+    let meta = trx.meta.as_ref().unwrap();
+
+    let mut deltas = Deltas::new();
+    for balance in &meta.pre_token_balances {
+        add_delta(
+            &mut deltas,
+            &balance_account(trx, balance),
+            &balance.mint,
+            balance_amount(balance).neg(),
+        );
+    }
+    for balance in &meta.post_token_balances {
+        add_delta(
+            &mut deltas,
+            &balance_account(trx, balance),
+            &balance.mint,
+            balance_amount(balance),
+        );
+    }
+
+    deltas
+}
+
+fn balance_account(trx: &ConfirmedTransaction, balance: &TokenBalance) -> String {
+    trx.account_at(balance.account_index as u8).to_string()
+}
+
+fn balance_amount(balance: &TokenBalance) -> BigInt {
+    balance
+        .ui_token_amount
+        .as_ref()
+        .map(|ui_amount| parse_raw_amount(&ui_amount.amount))
+        .unwrap_or_default()
+}
@@ -0,0 +1,51 @@
+use crate::parse_raw_amount;
+use crate::pb::sf::solana::spl::v1::r#type::{instruction::Item, SplInstructions, SupplySnapshot, SupplySnapshots};
+use std::collections::HashMap;
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+use substreams::store::{DeltaBigInt, Deltas, StoreAdd, StoreAddBigInt, StoreNew};
+
+/// Accumulates the supply of each mint in base units, mints minus burns, keyed by mint.
+#[substreams::handlers::store]
+fn store_mint_supply(spl_instructions: SplInstructions, store: StoreAddBigInt) {
+    for (ordinal, instruction) in spl_instructions.instructions.iter().enumerate() {
+        if !instruction.succeeded {
+            continue;
+        }
+
+        match &instruction.item {
+            Some(Item::Mint(mint)) => {
+                store.add(ordinal as u64, &mint.mint, parse_raw_amount(&mint.raw_amount));
+            }
+            Some(Item::Burn(burn)) => {
+                store.add(ordinal as u64, &burn.mint, parse_raw_amount(&burn.raw_amount).neg());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Emits the supply at the end of the block of each mint whose supply changed in the block.
+#[substreams::handlers::map]
+fn map_supply_snapshots(clock: Clock, deltas: Deltas<DeltaBigInt>) -> Result<SupplySnapshots, Error> {
+    let mut supply_snapshots: Vec<SupplySnapshot> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for delta in deltas.deltas {
+        if let Some(position) = positions.get(&delta.key) {
+            supply_snapshots[*position].supply = delta.new_value.to_string();
+            continue;
+        }
+
+        positions.insert(delta.key.clone(), supply_snapshots.len());
+        supply_snapshots.push(SupplySnapshot {
+            mint: delta.key,
+            slot: clock.number,
+            block_time: clock.timestamp,
+            supply: delta.new_value.to_string(),
+            previous_supply: delta.old_value.to_string(),
+        });
+    }
+
+    Ok(SupplySnapshots { supply_snapshots })
+}
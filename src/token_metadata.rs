@@ -0,0 +1,321 @@
+use crate::pb::sf::solana::spl::v1::r#type::{AdditionalMetadata, TokenMetadata, TokenMetadatas};
+use crate::{transactions_owned, SOLANA_TOKEN_2022_PROGRAM};
+use std::collections::HashSet;
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+use substreams::store::{
+    Appender, StoreAppend, StoreDelete, StoreGet, StoreGetArray, StoreGetString, StoreNew, StoreSet, StoreSetString,
+};
+use substreams_solana::pb::sf::solana::r#type::v1::Block;
+
+/// Discriminators of the token-metadata interface instructions, the first 8 bytes of the SHA-256 of
+/// `spl_token_metadata_interface:<name>`.
+const INITIALIZE: [u8; 8] = [210, 225, 30, 162, 88, 184, 77, 141];
+const UPDATE_FIELD: [u8; 8] = [221, 233, 49, 45, 181, 202, 220, 200];
+const REMOVE_KEY: [u8; 8] = [234, 18, 32, 56, 89, 141, 37, 181];
+const UPDATE_AUTHORITY: [u8; 8] = [215, 228, 166, 228, 84, 100, 86, 123];
+const EMIT: [u8; 8] = [250, 166, 180, 250, 13, 12, 184, 70];
+
+const FIELD_NAME: &str = "name";
+const FIELD_SYMBOL: &str = "symbol";
+const FIELD_URI: &str = "uri";
+const FIELD_AUTHORITY: &str = "authority";
+
+/// Field targeted by `UpdateField`.
+pub enum Field {
+    Name,
+    Symbol,
+    Uri,
+    Key(String),
+}
+
+/// Token-metadata interface instructions, implemented by Token-2022 for metadata stored in the mint
+/// itself. Accounts: 0. metadata (the mint), 1. update authority, and for `Initialize` 2. mint,
+/// 3. mint authority.
+pub enum TokenMetadataInstruction {
+    Initialize { name: String, symbol: String, uri: String },
+    UpdateField { field: Field, value: String },
+    RemoveKey { key: String },
+    UpdateAuthority { new_authority: Option<String> },
+    Emit,
+}
+
+impl TokenMetadataInstruction {
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let discriminator: [u8; 8] = data.get(..8)?.try_into().ok()?;
+        let mut reader = Reader(&data[8..]);
+
+        Some(match discriminator {
+            INITIALIZE => Self::Initialize {
+                name: reader.string()?,
+                symbol: reader.string()?,
+                uri: reader.string()?,
+            },
+            UPDATE_FIELD => Self::UpdateField {
+                field: match reader.u8()? {
+                    0 => Field::Name,
+                    1 => Field::Symbol,
+                    2 => Field::Uri,
+                    3 => Field::Key(reader.string()?),
+                    _ => return None,
+                },
+                value: reader.string()?,
+            },
+            REMOVE_KEY => {
+                // Skips the `idempotent` flag.
+                reader.u8()?;
+                Self::RemoveKey { key: reader.string()? }
+            }
+            UPDATE_AUTHORITY => {
+                let new_authority = reader.take(32)?;
+                Self::UpdateAuthority {
+                    new_authority: if new_authority.iter().all(|byte| *byte == 0) {
+                        None
+                    } else {
+                        Some(bs58::encode(new_authority).into_string())
+                    },
+                }
+            }
+            EMIT => Self::Emit,
+            _ => return None,
+        })
+    }
+}
+
+/// Minimal borsh reader, strings being prefixed by their `u32` little endian length.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+        self.take(len as usize)
+            .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+    }
+}
+
+/// A token-metadata instruction executed by Token-2022 on `mint`, along with the update authority
+/// account it was signed by.
+struct MetadataUpdate {
+    mint: String,
+    authority: String,
+    instruction: TokenMetadataInstruction,
+}
+
+/// Returns the token-metadata instructions of the block, in order.
+fn metadata_updates(block: Block) -> Vec<MetadataUpdate> {
+    let mut updates = vec![];
+    for (_, confirmed_trx) in transactions_owned(block, false) {
+        for instruction in confirmed_trx.walk_instructions() {
+            if instruction.program_id().to_string() != SOLANA_TOKEN_2022_PROGRAM {
+                continue;
+            }
+
+            let instruction_data = match TokenMetadataInstruction::unpack(instruction.data()) {
+                None | Some(TokenMetadataInstruction::Emit) => continue,
+                Some(instruction_data) => instruction_data,
+            };
+
+            let accounts = instruction.accounts();
+            updates.push(MetadataUpdate {
+                mint: accounts[0].to_string(),
+                authority: accounts[1].to_string(),
+                instruction: instruction_data,
+            });
+        }
+    }
+
+    updates
+}
+
+fn field_key(field: &str, mint: &str) -> String {
+    format!("{}:{}", field, mint)
+}
+
+/// Prefix of the additional fields of `mint` in `store_token_metadata`.
+fn additional_fields_prefix(mint: &str) -> String {
+    format!("field:{}:", mint)
+}
+
+/// Key of the additional field `key` of `mint` in `store_token_metadata`. The key is base58 encoded
+/// and terminated so that deleting it by prefix does not delete longer keys starting with it.
+fn additional_field_key(mint: &str, key: &str) -> String {
+    format!("{}{}:", additional_fields_prefix(mint), bs58::encode(key).into_string())
+}
+
+/// Records the token metadata of each mint, keyed by `name:<mint>`, `symbol:<mint>`, `uri:<mint>`,
+/// `authority:<mint>` and `field:<mint>:<base58 key>:` for additional fields.
+#[substreams::handlers::store]
+fn store_token_metadata(block: Block, store: StoreSetString) {
+    for (ordinal, update) in metadata_updates(block).into_iter().enumerate() {
+        let ordinal = ordinal as u64;
+        let mint = &update.mint;
+
+        match update.instruction {
+            TokenMetadataInstruction::Initialize { name, symbol, uri } => {
+                store.delete_prefix(ordinal as i64, &additional_fields_prefix(mint));
+                store.set(ordinal, field_key(FIELD_NAME, mint), &name);
+                store.set(ordinal, field_key(FIELD_SYMBOL, mint), &symbol);
+                store.set(ordinal, field_key(FIELD_URI, mint), &uri);
+                store.set(ordinal, field_key(FIELD_AUTHORITY, mint), &update.authority);
+            }
+            TokenMetadataInstruction::UpdateField { field, value } => {
+                let key = match field {
+                    Field::Name => field_key(FIELD_NAME, mint),
+                    Field::Symbol => field_key(FIELD_SYMBOL, mint),
+                    Field::Uri => field_key(FIELD_URI, mint),
+                    Field::Key(key) => additional_field_key(mint, &key),
+                };
+                store.set(ordinal, key, &value);
+            }
+            TokenMetadataInstruction::RemoveKey { key } => {
+                store.delete_prefix(ordinal as i64, &additional_field_key(mint, &key));
+            }
+            TokenMetadataInstruction::UpdateAuthority { new_authority } => {
+                store.set(
+                    ordinal,
+                    field_key(FIELD_AUTHORITY, mint),
+                    &new_authority.unwrap_or_default(),
+                );
+            }
+            TokenMetadataInstruction::Emit => {}
+        }
+    }
+}
+
+/// Records, keyed by mint, the base58 encoded keys of the additional fields ever set on it, which
+/// `store_token_metadata` cannot list.
+#[substreams::handlers::store]
+fn store_token_metadata_keys(block: Block, store: StoreAppend<String>) {
+    for (ordinal, update) in metadata_updates(block).into_iter().enumerate() {
+        if let TokenMetadataInstruction::UpdateField {
+            field: Field::Key(key), ..
+        } = update.instruction
+        {
+            store.append(ordinal as u64, update.mint, bs58::encode(key).into_string());
+        }
+    }
+}
+
+/// Emits the token metadata at the end of the block of each mint whose metadata was initialized or
+/// updated in the block.
+#[substreams::handlers::map]
+fn map_token_metadata(
+    clock: Clock,
+    block: Block,
+    store: StoreGetString,
+    keys_store: StoreGetArray<String>,
+) -> Result<TokenMetadatas, Error> {
+    let mut seen = HashSet::new();
+    let mut token_metadata = vec![];
+
+    for update in metadata_updates(block) {
+        if !seen.insert(update.mint.clone()) {
+            continue;
+        }
+
+        let mint = update.mint;
+        let field = |field: &str| store.get_last(field_key(field, &mint)).unwrap_or_default();
+
+        let mut keys = HashSet::new();
+        let mut additional_metadata = vec![];
+        for encoded_key in keys_store.get_last(&mint).unwrap_or_default() {
+            if !keys.insert(encoded_key.clone()) {
+                continue;
+            }
+
+            let key = bs58::decode(&encoded_key)
+                .into_vec()
+                .map_err(|err| anyhow::anyhow!("invalid token metadata key {}: {}", encoded_key, err))?;
+            let key = String::from_utf8_lossy(&key).to_string();
+            if let Some(value) = store.get_last(additional_field_key(&mint, &key)) {
+                additional_metadata.push(AdditionalMetadata { key, value });
+            }
+        }
+
+        token_metadata.push(TokenMetadata {
+            slot: clock.number,
+            block_time: clock.timestamp,
+            update_authority: field(FIELD_AUTHORITY),
+            name: field(FIELD_NAME),
+            symbol: field(FIELD_SYMBOL),
+            uri: field(FIELD_URI),
+            additional_metadata,
+            mint,
+        });
+    }
+
+    Ok(TokenMetadatas { token_metadata })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borsh_string(value: &str) -> Vec<u8> {
+        let mut bytes = (value.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn unpack() {
+        let mut data = INITIALIZE.to_vec();
+        data.extend(borsh_string("Token"));
+        data.extend(borsh_string("TKN"));
+        data.extend(borsh_string("https://example.com"));
+        let Some(TokenMetadataInstruction::Initialize { name, symbol, uri }) = TokenMetadataInstruction::unpack(&data)
+        else {
+            panic!("expected Initialize");
+        };
+        assert_eq!(
+            (name.as_str(), symbol.as_str(), uri.as_str()),
+            ("Token", "TKN", "https://example.com")
+        );
+
+        let mut data = UPDATE_FIELD.to_vec();
+        data.push(3);
+        data.extend(borsh_string("color"));
+        data.extend(borsh_string("blue"));
+        let Some(TokenMetadataInstruction::UpdateField {
+            field: Field::Key(key),
+            value,
+        }) = TokenMetadataInstruction::unpack(&data)
+        else {
+            panic!("expected UpdateField of an additional key");
+        };
+        assert_eq!((key.as_str(), value.as_str()), ("color", "blue"));
+
+        let mut data = REMOVE_KEY.to_vec();
+        data.push(1);
+        data.extend(borsh_string("color"));
+        let Some(TokenMetadataInstruction::RemoveKey { key }) = TokenMetadataInstruction::unpack(&data) else {
+            panic!("expected RemoveKey");
+        };
+        assert_eq!(key, "color");
+
+        let mut data = UPDATE_AUTHORITY.to_vec();
+        data.extend([0; 32]);
+        assert!(matches!(
+            TokenMetadataInstruction::unpack(&data),
+            Some(TokenMetadataInstruction::UpdateAuthority { new_authority: None })
+        ));
+
+        // Truncated data and unknown discriminators are not token-metadata instructions.
+        assert!(TokenMetadataInstruction::unpack(&INITIALIZE).is_none());
+        assert!(TokenMetadataInstruction::unpack(&[0; 8]).is_none());
+    }
+}
@@ -0,0 +1,174 @@
+use substreams::Hex;
+use substreams_solana::pb::sf::solana::r#type::v1::TransactionError;
+
+/// Variants of Solana's `TransactionError`, in declaration order which is their bincode tag.
+const TRANSACTION_ERRORS: &[&str] = &[
+    "AccountInUse",
+    "AccountLoadedTwice",
+    "AccountNotFound",
+    "ProgramAccountNotFound",
+    "InsufficientFundsForFee",
+    "InvalidAccountForFee",
+    "AlreadyProcessed",
+    "BlockhashNotFound",
+    "InstructionError",
+    "CallChainTooDeep",
+    "MissingSignatureForFee",
+    "InvalidAccountIndex",
+    "SignatureFailure",
+    "InvalidProgramForExecution",
+    "SanitizeFailure",
+    "ClusterMaintenance",
+    "AccountBorrowOutstanding",
+    "WouldExceedMaxBlockCostLimit",
+    "UnsupportedVersion",
+    "InvalidWritableAccount",
+    "WouldExceedMaxAccountCostLimit",
+    "WouldExceedAccountDataBlockLimit",
+    "TooManyAccountLocks",
+    "AddressLookupTableNotFound",
+    "InvalidAddressLookupTableOwner",
+    "InvalidAddressLookupTableData",
+    "InvalidAddressLookupTableIndex",
+    "InvalidRentPayingAccount",
+    "WouldExceedMaxVoteCostLimit",
+    "WouldExceedAccountDataTotalLimit",
+    "DuplicateInstruction",
+    "InsufficientFundsForRent",
+    "MaxLoadedAccountsDataSizeExceeded",
+    "InvalidLoadedAccountsDataSizeLimit",
+    "ResanitizationNeeded",
+    "ProgramExecutionTemporarilyRestricted",
+    "UnbalancedTransaction",
+    "ProgramCacheHitMaxLimit",
+];
+
+const TRANSACTION_ERROR_INSTRUCTION_ERROR: u32 = 8;
+const TRANSACTION_ERROR_DUPLICATE_INSTRUCTION: u32 = 30;
+const TRANSACTION_ERROR_INSUFFICIENT_FUNDS_FOR_RENT: u32 = 31;
+const TRANSACTION_ERROR_PROGRAM_EXECUTION_TEMPORARILY_RESTRICTED: u32 = 35;
+
+/// Variants of Solana's `InstructionError`, in declaration order which is their bincode tag.
+const INSTRUCTION_ERRORS: &[&str] = &[
+    "GenericError",
+    "InvalidArgument",
+    "InvalidInstructionData",
+    "InvalidAccountData",
+    "AccountDataTooSmall",
+    "InsufficientFunds",
+    "IncorrectProgramId",
+    "MissingRequiredSignature",
+    "AccountAlreadyInitialized",
+    "UninitializedAccount",
+    "UnbalancedInstruction",
+    "ModifiedProgramId",
+    "ExternalAccountLamportSpend",
+    "ExternalAccountDataModified",
+    "ReadonlyLamportChange",
+    "ReadonlyDataModified",
+    "DuplicateAccountIndex",
+    "ExecutableModified",
+    "RentEpochModified",
+    "NotEnoughAccountKeys",
+    "AccountDataSizeChanged",
+    "AccountNotExecutable",
+    "AccountBorrowFailed",
+    "AccountBorrowOutstanding",
+    "DuplicateAccountOutOfSync",
+    "Custom",
+    "InvalidError",
+    "ExecutableDataModified",
+    "ExecutableLamportChange",
+    "ExecutableAccountNoRentEpoch",
+    "UnsupportedProgramId",
+    "CallDepth",
+    "MissingAccount",
+    "ReentrancyNotAllowed",
+    "MaxSeedLengthExceeded",
+    "InvalidSeeds",
+    "InvalidRealloc",
+    "ComputationalBudgetExceeded",
+    "PrivilegeEscalation",
+    "ProgramEnvironmentSetupFailure",
+    "ProgramFailedToComplete",
+    "ProgramFailedToCompile",
+    "Immutable",
+    "IncorrectAuthority",
+    "BorshIoError",
+    "AccountNotRentExempt",
+    "InvalidAccountOwner",
+    "ArithmeticOverflow",
+    "UnsupportedSysvar",
+    "IllegalOwner",
+    "MaxAccountsDataAllocationsExceeded",
+    "MaxAccountsExceeded",
+    "MaxInstructionTraceLengthExceeded",
+    "BuiltinProgramsMustConsumeComputeUnits",
+];
+
+const INSTRUCTION_ERROR_CUSTOM: u32 = 25;
+const INSTRUCTION_ERROR_BORSH_IO_ERROR: u32 = 44;
+
+/// Renders a transaction error, carried bincode encoded in the transaction's meta, the way Solana
+/// displays it in its debug form, e.g. `InstructionError(2, Custom(1))`. Errors that cannot be
+/// decoded are rendered as the hex encoding of their raw bytes.
+pub fn transaction_error_to_string(error: &TransactionError) -> String {
+    decode_transaction_error(&mut Reader(&error.err)).unwrap_or_else(|| Hex(&error.err).to_string())
+}
+
+fn decode_transaction_error(reader: &mut Reader) -> Option<String> {
+    let tag = reader.u32()?;
+    let name = TRANSACTION_ERRORS.get(tag as usize)?;
+
+    Some(match tag {
+        TRANSACTION_ERROR_INSTRUCTION_ERROR => {
+            let index = reader.u8()?;
+            format!("{}({}, {})", name, index, decode_instruction_error(reader)?)
+        }
+        TRANSACTION_ERROR_DUPLICATE_INSTRUCTION => format!("{}({})", name, reader.u8()?),
+        TRANSACTION_ERROR_INSUFFICIENT_FUNDS_FOR_RENT | TRANSACTION_ERROR_PROGRAM_EXECUTION_TEMPORARILY_RESTRICTED => {
+            format!("{} {{ account_index: {} }}", name, reader.u8()?)
+        }
+        _ => name.to_string(),
+    })
+}
+
+fn decode_instruction_error(reader: &mut Reader) -> Option<String> {
+    let tag = reader.u32()?;
+    let name = INSTRUCTION_ERRORS.get(tag as usize)?;
+
+    Some(match tag {
+        INSTRUCTION_ERROR_CUSTOM => format!("{}({})", name, reader.u32()?),
+        INSTRUCTION_ERROR_BORSH_IO_ERROR => format!("{}({:?})", name, reader.string()?),
+        _ => name.to_string(),
+    })
+}
+
+/// Minimal bincode reader, integers being fixed size little endian.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = u64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        self.take(len as usize)
+            .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+    }
+}
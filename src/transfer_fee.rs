@@ -0,0 +1,219 @@
+use crate::params::Params;
+use crate::pb::sf::solana::spl::v1::r#type::{instruction::Item, Instruction, SplParams};
+use crate::{amount_to_decimals, transactions_owned, SOLANA_TOKEN_2022_PROGRAM};
+use substreams::store::{Appender, StoreAppend, StoreGet, StoreGetArray};
+use substreams_solana::pb::sf::solana::r#type::v1::Block;
+use substreams_solana_program_instructions::token_instruction_2022::TokenInstruction;
+use substreams_solana_program_instructions::transfer_fee_instruction::TransferFeeInstruction;
+
+/// Slots per epoch on mainnet, which has no warmup period.
+const SLOTS_PER_EPOCH: u64 = 432_000;
+const MAX_FEE_BASIS_POINTS: u128 = 10_000;
+
+fn epoch(slot: u64) -> u64 {
+    slot / SLOTS_PER_EPOCH
+}
+
+fn transfer_fee_key(mint: &str) -> String {
+    format!("transfer_fee:{}", mint)
+}
+
+/// Records the history of the transfer fees of the tracked Token-2022 mints (`transfer_fee:<mint>`
+/// keys, `<epoch>:<basis_points>:<maximum_fee>` entries) from `InitializeTransferFeeConfig` and
+/// `SetTransferFee`, the first entry of each key being the initialization.
+#[substreams::handlers::store]
+fn store_transfer_fee_configs(params: SplParams, block: Block, store: StoreAppend<String>) {
+    let tracked_mints = Params::from(params).tracked_mints;
+    let epoch = epoch(block.slot);
+
+    let mut ordinal = 0;
+    for (_, confirmed_trx) in transactions_owned(block, false) {
+        for instruction in confirmed_trx.walk_instructions() {
+            if instruction.program_id().to_string() != SOLANA_TOKEN_2022_PROGRAM {
+                continue;
+            }
+
+            let (transfer_fee_basis_points, maximum_fee) = match TokenInstruction::unpack(instruction.data()) {
+                Ok(TokenInstruction::TransferFeeExtension(TransferFeeInstruction::InitializeTransferFeeConfig {
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                    ..
+                }))
+                | Ok(TokenInstruction::TransferFeeExtension(TransferFeeInstruction::SetTransferFee {
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                })) => (transfer_fee_basis_points, maximum_fee),
+                _ => continue,
+            };
+            let Some(mint) = instruction.accounts().first().map(|account| account.to_string()) else {
+                continue;
+            };
+            if !tracked_mints.contains(&mint) {
+                continue;
+            }
+
+            store.append(
+                ordinal,
+                transfer_fee_key(&mint),
+                format!("{}:{}:{}", epoch, transfer_fee_basis_points, maximum_fee),
+            );
+            ordinal += 1;
+        }
+    }
+}
+
+/// Transfer fee in effect from `epoch`, mirroring Token-2022's `TransferFee`.
+#[derive(Clone, Copy)]
+struct TransferFee {
+    epoch: u64,
+    basis_points: u16,
+    maximum_fee: u64,
+}
+
+impl TransferFee {
+    fn calculate_fee(&self, amount: u64) -> u64 {
+        if self.basis_points == 0 || amount == 0 {
+            return 0;
+        }
+
+        let numerator = amount as u128 * self.basis_points as u128;
+        let fee = numerator.div_ceil(MAX_FEE_BASIS_POINTS);
+        u64::try_from(fee).unwrap_or(u64::MAX).min(self.maximum_fee)
+    }
+}
+
+/// State of a mint's transfer fee extension, mirroring Token-2022's `TransferFeeConfig`. A new fee
+/// only takes effect two epochs after being set, the older one applying until then.
+struct TransferFeeConfig {
+    older_transfer_fee: TransferFee,
+    newer_transfer_fee: TransferFee,
+}
+
+impl TransferFeeConfig {
+    fn new(epoch: u64, basis_points: u16, maximum_fee: u64) -> Self {
+        let transfer_fee = TransferFee {
+            epoch,
+            basis_points,
+            maximum_fee,
+        };
+
+        Self {
+            older_transfer_fee: transfer_fee,
+            newer_transfer_fee: transfer_fee,
+        }
+    }
+
+    fn set_transfer_fee(&mut self, epoch: u64, basis_points: u16, maximum_fee: u64) {
+        if self.newer_transfer_fee.epoch <= epoch {
+            self.older_transfer_fee = self.newer_transfer_fee;
+        }
+
+        self.newer_transfer_fee = TransferFee {
+            epoch: epoch + 2,
+            basis_points,
+            maximum_fee,
+        };
+    }
+
+    fn calculate_epoch_fee(&self, epoch: u64, amount: u64) -> u64 {
+        if epoch >= self.newer_transfer_fee.epoch {
+            self.newer_transfer_fee.calculate_fee(amount)
+        } else {
+            self.older_transfer_fee.calculate_fee(amount)
+        }
+    }
+}
+
+/// Computes the fees Token-2022 withholds on transfers of mints with a transfer fee from the history
+/// recorded in `store_transfer_fee_configs`.
+pub struct TransferFees<'a> {
+    store: &'a StoreGetArray<String>,
+}
+
+impl<'a> TransferFees<'a> {
+    pub fn new(store: &'a StoreGetArray<String>) -> Self {
+        Self { store }
+    }
+
+    /// Returns the fee withheld on a transfer of `amount` of `mint` at `epoch`, `None` for mints
+    /// without a transfer fee.
+    fn fee(&self, mint: &str, epoch: u64, amount: u64) -> Option<u64> {
+        let mut config: Option<TransferFeeConfig> = None;
+        for entry in self.store.get_last(transfer_fee_key(mint))? {
+            let mut fields = entry.split(':');
+            let at = fields.next().and_then(|field| field.parse().ok()).unwrap_or_default();
+            let basis_points = fields.next().and_then(|field| field.parse().ok()).unwrap_or_default();
+            let maximum_fee = fields.next().and_then(|field| field.parse().ok()).unwrap_or_default();
+            match config.as_mut() {
+                None => config = Some(TransferFeeConfig::new(at, basis_points, maximum_fee)),
+                Some(config) => config.set_transfer_fee(at, basis_points, maximum_fee),
+            }
+        }
+
+        config.map(|config| config.calculate_epoch_fee(epoch, amount))
+    }
+
+    /// Sets the fee and net amount of Token-2022 `Transfer` and `TransferChecked` instructions, which
+    /// unlike `TransferCheckedWithFee` do not carry the fee Token-2022 withholds on fee-bearing mints.
+    pub fn apply(&self, instructions: &mut [Instruction]) {
+        for instruction in instructions {
+            if instruction.token_program != SOLANA_TOKEN_2022_PROGRAM {
+                continue;
+            }
+            let epoch = epoch(instruction.slot);
+            let Some(Item::Transfer(ref mut transfer)) = instruction.item else {
+                continue;
+            };
+            if transfer.raw_fee != "0" {
+                continue;
+            }
+
+            let amount: u64 = transfer.raw_amount.parse().unwrap_or_default();
+            let Some(fee) = self.fee(&transfer.mint, epoch, amount) else {
+                continue;
+            };
+            let net_amount = amount.saturating_sub(fee);
+
+            transfer.fee = amount_to_decimals(fee as f64, transfer.decimals as f64);
+            transfer.raw_fee = fee.to_string();
+            transfer.net_amount = amount_to_decimals(net_amount as f64, transfer.decimals as f64);
+            transfer.raw_net_amount = net_amount.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculate_fee() {
+        let transfer_fee = TransferFee {
+            epoch: 0,
+            basis_points: 250,
+            maximum_fee: 5_000,
+        };
+
+        assert_eq!(transfer_fee.calculate_fee(0), 0);
+        // 2.5% of 1 rounds up to 1.
+        assert_eq!(transfer_fee.calculate_fee(1), 1);
+        assert_eq!(transfer_fee.calculate_fee(10_000), 250);
+        assert_eq!(transfer_fee.calculate_fee(10_001), 251);
+        assert_eq!(transfer_fee.calculate_fee(1_000_000), 5_000);
+    }
+
+    #[test]
+    fn new_transfer_fee_takes_effect_two_epochs_later() {
+        let mut config = TransferFeeConfig::new(10, 100, u64::MAX);
+        config.set_transfer_fee(12, 200, u64::MAX);
+
+        assert_eq!(config.calculate_epoch_fee(12, 10_000), 100);
+        assert_eq!(config.calculate_epoch_fee(13, 10_000), 100);
+        assert_eq!(config.calculate_epoch_fee(14, 10_000), 200);
+
+        // Set again before the previous fee took effect, the older fee still applies until then.
+        config.set_transfer_fee(13, 300, u64::MAX);
+        assert_eq!(config.calculate_epoch_fee(14, 10_000), 100);
+        assert_eq!(config.calculate_epoch_fee(15, 10_000), 300);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::params::Params;
+use crate::pb::sf::solana::spl::v1::r#type::{instruction::Item, Instruction, SplParams};
+use crate::{is_token_program, transactions_owned};
+use substreams::store::{Appender, StoreAppend, StoreGet, StoreGetArray};
+use substreams_solana::pb::sf::solana::r#type::v1::Block;
+
+const INTEREST_BEARING_MINT_EXTENSION: u8 = 33;
+const SCALED_UI_AMOUNT_EXTENSION: u8 = 43;
+
+const SECONDS_PER_YEAR: f64 = 60. * 60. * 24. * 365.24;
+const ONE_IN_BASIS_POINTS: f64 = 10_000.;
+
+/// Token-2022 instructions changing how the UI amount of a mint is computed. They are decoded here
+/// as `TokenInstruction::unpack` only exposes the interest-bearing extension's tag and does not know
+/// the scaled UI amount extension. Initialization data starts with the 32 bytes authority, skipped.
+pub enum UiAmountInstruction {
+    /// Accounts: 0. mint.
+    InitializeInterestBearingMint { rate: i16 },
+    /// Accounts: 0. mint, 1. rate authority.
+    UpdateRate { rate: i16 },
+    /// Accounts: 0. mint.
+    InitializeScaledUiAmount { multiplier: f64 },
+    /// Accounts: 0. mint, 1. multiplier authority.
+    UpdateMultiplier { multiplier: f64, effective_timestamp: i64 },
+}
+
+impl UiAmountInstruction {
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let (&tag, rest) = data.split_first()?;
+        let (&sub_tag, rest) = rest.split_first()?;
+
+        match (tag, sub_tag) {
+            (INTEREST_BEARING_MINT_EXTENSION, 0) => Some(Self::InitializeInterestBearingMint {
+                rate: i16::from_le_bytes(rest.get(32..34)?.try_into().ok()?),
+            }),
+            (INTEREST_BEARING_MINT_EXTENSION, 1) => Some(Self::UpdateRate {
+                rate: i16::from_le_bytes(rest.get(..2)?.try_into().ok()?),
+            }),
+            (SCALED_UI_AMOUNT_EXTENSION, 0) => Some(Self::InitializeScaledUiAmount {
+                multiplier: f64::from_le_bytes(rest.get(32..40)?.try_into().ok()?),
+            }),
+            (SCALED_UI_AMOUNT_EXTENSION, 1) => Some(Self::UpdateMultiplier {
+                multiplier: f64::from_le_bytes(rest.get(..8)?.try_into().ok()?),
+                effective_timestamp: i64::from_le_bytes(rest.get(8..16)?.try_into().ok()?),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Records the history of interest rates (`interest:<mint>` keys, `<timestamp>:<rate>` entries) and
+/// of scaled UI amount multipliers (`scaled:<mint>` keys, `<timestamp>:<multiplier>:<effective_timestamp>`
+/// entries) of the tracked Token-2022 mints, the first entry of each key being the initialization.
+#[substreams::handlers::store]
+fn store_ui_amount_configs(params: SplParams, block: Block, store: StoreAppend<String>) {
+    let tracked_mints = Params::from(params).tracked_mints;
+    let timestamp = block.block_time.as_ref().map(|time| time.timestamp).unwrap_or(0);
+
+    let mut ordinal = 0;
+    for (_, confirmed_trx) in transactions_owned(block, false) {
+        for instruction in confirmed_trx.walk_instructions() {
+            if !is_token_program(&instruction.program_id().to_string()) {
+                continue;
+            }
+
+            let Some(ui_amount_instruction) = UiAmountInstruction::unpack(instruction.data()) else {
+                continue;
+            };
+
+            let mint = instruction.accounts()[0].to_string();
+            if !tracked_mints.contains(&mint) {
+                continue;
+            }
+
+            let (key, entry) = match ui_amount_instruction {
+                UiAmountInstruction::InitializeInterestBearingMint { rate }
+                | UiAmountInstruction::UpdateRate { rate } => {
+                    (format!("interest:{}", mint), format!("{}:{}", timestamp, rate))
+                }
+                UiAmountInstruction::InitializeScaledUiAmount { multiplier } => (
+                    format!("scaled:{}", mint),
+                    format!("{}:{}:{}", timestamp, multiplier, timestamp),
+                ),
+                UiAmountInstruction::UpdateMultiplier {
+                    multiplier,
+                    effective_timestamp,
+                } => (
+                    format!("scaled:{}", mint),
+                    format!("{}:{}:{}", timestamp, multiplier, effective_timestamp),
+                ),
+            };
+
+            store.append(ordinal, key, entry);
+            ordinal += 1;
+        }
+    }
+}
+
+/// State of a mint's interest-bearing extension, mirroring Token-2022's `InterestBearingConfig`.
+struct InterestBearingConfig {
+    initialization_timestamp: i64,
+    pre_update_average_rate: i16,
+    last_update_timestamp: i64,
+    current_rate: i16,
+}
+
+impl InterestBearingConfig {
+    fn new(timestamp: i64, rate: i16) -> Self {
+        Self {
+            initialization_timestamp: timestamp,
+            pre_update_average_rate: rate,
+            last_update_timestamp: timestamp,
+            current_rate: rate,
+        }
+    }
+
+    fn update_rate(&mut self, timestamp: i64, rate: i16) {
+        let pre_update_timespan = (self.last_update_timestamp - self.initialization_timestamp) as i128;
+        let post_update_timespan = (timestamp - self.last_update_timestamp) as i128;
+        let total_timespan = pre_update_timespan + post_update_timespan;
+
+        if total_timespan != 0 {
+            let total_sum = self.pre_update_average_rate as i128 * pre_update_timespan
+                + self.current_rate as i128 * post_update_timespan;
+            self.pre_update_average_rate = (total_sum / total_timespan) as i16;
+        } else {
+            self.pre_update_average_rate = self.current_rate;
+        }
+
+        self.last_update_timestamp = timestamp;
+        self.current_rate = rate;
+    }
+
+    /// Returns the factor accrued interest multiplies amounts by at `timestamp`.
+    fn scale(&self, timestamp: i64) -> f64 {
+        let pre_update_exponent = self.pre_update_average_rate as f64
+            * (self.last_update_timestamp - self.initialization_timestamp) as f64
+            / SECONDS_PER_YEAR
+            / ONE_IN_BASIS_POINTS;
+        let post_update_exponent = self.current_rate as f64 * (timestamp - self.last_update_timestamp) as f64
+            / SECONDS_PER_YEAR
+            / ONE_IN_BASIS_POINTS;
+
+        pre_update_exponent.exp() * post_update_exponent.exp()
+    }
+}
+
+/// State of a mint's scaled UI amount extension, mirroring Token-2022's `ScaledUiAmountConfig`.
+struct ScaledUiAmountConfig {
+    multiplier: f64,
+    new_multiplier: f64,
+    new_multiplier_effective_timestamp: i64,
+}
+
+impl ScaledUiAmountConfig {
+    fn new(timestamp: i64, multiplier: f64) -> Self {
+        Self {
+            multiplier,
+            new_multiplier: multiplier,
+            new_multiplier_effective_timestamp: timestamp,
+        }
+    }
+
+    fn update_multiplier(&mut self, timestamp: i64, multiplier: f64, effective_timestamp: i64) {
+        if effective_timestamp <= timestamp {
+            self.multiplier = multiplier;
+        } else if self.new_multiplier_effective_timestamp <= timestamp {
+            self.multiplier = self.new_multiplier;
+        }
+
+        self.new_multiplier = multiplier;
+        self.new_multiplier_effective_timestamp = effective_timestamp;
+    }
+
+    fn scale(&self, timestamp: i64) -> f64 {
+        if timestamp >= self.new_multiplier_effective_timestamp {
+            self.new_multiplier
+        } else {
+            self.multiplier
+        }
+    }
+}
+
+/// Computes the UI amounts of interest-bearing and scaled UI amount mints from the history recorded
+/// in `store_ui_amount_configs`.
+pub struct UiAmountScales<'a> {
+    store: &'a StoreGetArray<String>,
+}
+
+impl<'a> UiAmountScales<'a> {
+    pub fn new(store: &'a StoreGetArray<String>) -> Self {
+        Self { store }
+    }
+
+    /// Returns the factor the UI amount of `mint` is multiplied by at `timestamp`, `None` for mints
+    /// without an interest-bearing or scaled UI amount extension.
+    fn scale(&self, mint: &str, timestamp: i64) -> Option<f64> {
+        let interest = self.store.get_last(format!("interest:{}", mint)).map(|entries| {
+            let mut config: Option<InterestBearingConfig> = None;
+            for entry in entries {
+                let mut fields = entry.split(':').map(|field| field.parse::<i64>().unwrap_or_default());
+                let (at, rate) = (
+                    fields.next().unwrap_or_default(),
+                    fields.next().unwrap_or_default() as i16,
+                );
+                match config.as_mut() {
+                    None => config = Some(InterestBearingConfig::new(at, rate)),
+                    Some(config) => config.update_rate(at, rate),
+                }
+            }
+
+            config.map(|config| config.scale(timestamp)).unwrap_or(1.)
+        });
+
+        let scaled = self.store.get_last(format!("scaled:{}", mint)).map(|entries| {
+            let mut config: Option<ScaledUiAmountConfig> = None;
+            for entry in entries {
+                let fields: Vec<&str> = entry.split(':').collect();
+                let at = fields.first().and_then(|field| field.parse().ok()).unwrap_or_default();
+                let multiplier = fields.get(1).and_then(|field| field.parse().ok()).unwrap_or(1.);
+                let effective_timestamp = fields.get(2).and_then(|field| field.parse().ok()).unwrap_or(at);
+                match config.as_mut() {
+                    None => config = Some(ScaledUiAmountConfig::new(at, multiplier)),
+                    Some(config) => config.update_multiplier(at, multiplier, effective_timestamp),
+                }
+            }
+
+            config.map(|config| config.scale(timestamp)).unwrap_or(1.)
+        });
+
+        match (interest, scaled) {
+            (None, None) => None,
+            (interest, scaled) => Some(interest.unwrap_or(1.) * scaled.unwrap_or(1.)),
+        }
+    }
+
+    /// Sets the UI amount of the instructions, their decimal-scaled amount multiplied, for
+    /// interest-bearing and scaled UI amount mints, by the scale in effect at the instruction's block time.
+    pub fn apply(&self, instructions: &mut [Instruction]) {
+        for instruction in instructions {
+            let timestamp = instruction.block_time.map(|time| time.seconds).unwrap_or(0);
+            let Some(ref mut item) = instruction.item else {
+                continue;
+            };
+
+            let (mint, amount, ui_amount) = match item {
+                Item::Transfer(transfer) => (&transfer.mint, transfer.amount, &mut transfer.ui_amount),
+                Item::Mint(mint) => (&mint.mint, mint.amount, &mut mint.ui_amount),
+                Item::Burn(burn) => (&burn.mint, burn.amount, &mut burn.ui_amount),
+                Item::Approve(approve) => (&approve.mint, approve.amount, &mut approve.ui_amount),
+                Item::WithdrawWithheldTokens(withdraw) => (&withdraw.mint, withdraw.amount, &mut withdraw.ui_amount),
+                Item::ConfidentialDeposit(deposit) => (&deposit.mint, deposit.amount, &mut deposit.ui_amount),
+                Item::ConfidentialWithdraw(withdraw) => (&withdraw.mint, withdraw.amount, &mut withdraw.ui_amount),
+                _ => continue,
+            };
+
+            *ui_amount = amount * self.scale(mint, timestamp).unwrap_or(1.);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `SECONDS_PER_YEAR` as an integer, as used by Token-2022's tests.
+    const ONE_YEAR: i64 = 6 * 6 * 24 * 36_524;
+
+    #[test]
+    fn interest_bearing_scale() {
+        // 5% a year for a year gives exp(0.05).
+        let mut config = InterestBearingConfig::new(0, 500);
+        config.update_rate(ONE_YEAR, 500);
+        assert_eq!(config.scale(ONE_YEAR), 1.0512710963760241);
+
+        // -5% a year for a year gives exp(-0.05).
+        let mut config = InterestBearingConfig::new(0, -500);
+        config.update_rate(ONE_YEAR, -500);
+        assert_eq!(config.scale(ONE_YEAR), 0.951229424500714);
+    }
+
+    #[test]
+    fn interest_bearing_scale_after_rate_update() {
+        // A year at 5% then a year at 10% gives exp(0.05) * exp(0.1).
+        let mut config = InterestBearingConfig::new(0, 500);
+        config.update_rate(ONE_YEAR, 1_000);
+
+        assert_eq!(config.pre_update_average_rate, 500);
+        assert!((config.scale(2 * ONE_YEAR) - 0.15_f64.exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn scaled_ui_amount_scale() {
+        // Multiplier 5, 10 from timestamp 1.
+        let mut config = ScaledUiAmountConfig::new(0, 5.);
+        config.update_multiplier(0, 10., 1);
+
+        assert_eq!(config.scale(0), 5.);
+        assert_eq!(config.scale(1), 10.);
+
+        // Effective immediately.
+        config.update_multiplier(2, 20., 2);
+        assert_eq!(config.scale(2), 20.);
+    }
+
+    #[test]
+    fn unpack() {
+        let mut data = vec![INTEREST_BEARING_MINT_EXTENSION, 0];
+        data.extend([0; 32]);
+        data.extend(500_i16.to_le_bytes());
+        assert!(matches!(
+            UiAmountInstruction::unpack(&data),
+            Some(UiAmountInstruction::InitializeInterestBearingMint { rate: 500 })
+        ));
+
+        let mut data = vec![SCALED_UI_AMOUNT_EXTENSION, 1];
+        data.extend(2.5_f64.to_le_bytes());
+        data.extend(1_700_000_000_i64.to_le_bytes());
+        assert!(matches!(
+            UiAmountInstruction::unpack(&data),
+            Some(UiAmountInstruction::UpdateMultiplier {
+                multiplier,
+                effective_timestamp: 1_700_000_000
+            }) if multiplier == 2.5
+        ));
+    }
+}
@@ -23,11 +23,15 @@ SELECT b.number    AS block_number,
        i.instruction_id as instruction_id,
        m.to        AS to_derive_address,
        a.owner    AS to_owner_address,
-       m.amount AS amount
+       m.amount AS amount,
+       m.raw_amount AS raw_amount,
+       m.decimals AS decimals,
+       m.mint AS mint
 FROM spl2.mints m
          INNER JOIN instructions i ON i.instruction_id = m.instruction_id
          INNER JOIN _blocks_ b ON b.number = i._block_number_
-         LEFT JOIN initialized_accounts a ON a.account = m."to";
+         LEFT JOIN initialized_accounts a ON a.account = m."to"
+WHERE i.succeeded;
 
 insert into mv_all_mints
 SELECT b.number    AS block_number,
@@ -35,11 +39,15 @@ SELECT b.number    AS block_number,
        i.instruction_id as instruction_id,
        m.to        AS to_derive_address,
        a.owner    AS to_owner_address,
-       m.amount AS amount
+       m.amount AS amount,
+       m.raw_amount AS raw_amount,
+       m.decimals AS decimals,
+       m.mint AS mint
 FROM spl2.mints m
          INNER JOIN spl2.instructions i ON i.instruction_id = m.instruction_id
          INNER JOIN spl2._blocks_ b ON b.number = i._block_number_
-         LEFT JOIN spl2.initialized_accounts a ON a.account = m."to";
+         LEFT JOIN spl2.initialized_accounts a ON a.account = m."to"
+WHERE i.succeeded;
 
 
 -- -----------------------------------------------
@@ -56,11 +64,15 @@ SELECT b.number    AS block_number,
        i.instruction_id AS instruction_id,
        br.from     AS from_derive_address,
        a.owner    AS from_owner_address,
-       br.amount AS amount
+       br.amount AS amount,
+       br.raw_amount AS raw_amount,
+       br.decimals AS decimals,
+       br.mint AS mint
 FROM spl2.burns br
          INNER JOIN spl2.instructions i ON i.instruction_id = br.instruction_id
          INNER JOIN spl2._blocks_ b ON b.number = i._block_number_
-         LEFT JOIN spl2.initialized_accounts a ON a.account = br."from";
+         LEFT JOIN spl2.initialized_accounts a ON a.account = br."from"
+WHERE i.succeeded;
 
 insert into mv_all_burns
 SELECT b.number    AS block_number,
@@ -68,11 +80,15 @@ SELECT b.number    AS block_number,
        i.instruction_id AS instruction_id,
        br.from     AS from_derive_address,
        a.owner    AS from_owner_address,
-       br.amount AS amount
+       br.amount AS amount,
+       br.raw_amount AS raw_amount,
+       br.decimals AS decimals,
+       br.mint AS mint
 FROM spl2.burns br
          INNER JOIN spl2.instructions i ON i.instruction_id = br.instruction_id
          INNER JOIN spl2._blocks_ b ON b.number = i._block_number_
-         LEFT JOIN spl2.initialized_accounts a ON a.account = br."from";
+         LEFT JOIN spl2.initialized_accounts a ON a.account = br."from"
+WHERE i.succeeded;
 -- -----------------------------------------------
 -- -----------------------------------------------
 -- -----------------------------------------------
@@ -80,18 +96,22 @@ FROM spl2.burns br
 CREATE MATERIALIZED VIEW mv_mint_per_month
     ENGINE = ReplacingMergeTree()
     PARTITION BY toYYYYMM(month)
-    ORDER BY (month)
+    ORDER BY (month, mint)
 AS
 SELECT DATE_TRUNC('month', m.block_time) as month,
-       sum(m.amount)                     as total
+       m.mint                            as mint,
+       sum(m.amount)                     as total,
+       sum(m.raw_amount)                 as raw_total
 FROM spl2.mv_all_mints m
-GROUP BY DATE_TRUNC('month', m.block_time);
+GROUP BY DATE_TRUNC('month', m.block_time), m.mint;
 
 insert into mv_mint_per_month
 SELECT DATE_TRUNC('month', m.block_time) as month,
-       sum(m.amount)                     as total
+       m.mint                            as mint,
+       sum(m.amount)                     as total,
+       sum(m.raw_amount)                 as raw_total
 FROM spl2.mv_all_mints m
-GROUP BY DATE_TRUNC('month', m.block_time);
+GROUP BY DATE_TRUNC('month', m.block_time), m.mint;
 
 
 -- -----------------------------------------------
@@ -102,18 +122,22 @@ GROUP BY DATE_TRUNC('month', m.block_time);
 CREATE MATERIALIZED VIEW mv_burn_per_month
     ENGINE = ReplacingMergeTree()
     PARTITION BY toYYYYMM(month)
-    ORDER BY (month)
+    ORDER BY (month, mint)
 AS
 SELECT DATE_TRUNC('month', m.block_time) as month,
-       sum(m.amount)                     as total
+       m.mint                            as mint,
+       sum(m.amount)                     as total,
+       sum(m.raw_amount)                 as raw_total
 FROM spl2.mv_all_burns m
-GROUP BY DATE_TRUNC('month', m.block_time);
+GROUP BY DATE_TRUNC('month', m.block_time), m.mint;
 
 insert into mv_burn_per_month
 SELECT DATE_TRUNC('month', m.block_time) as month,
-       sum(m.amount)                     as total
+       m.mint                            as mint,
+       sum(m.amount)                     as total,
+       sum(m.raw_amount)                 as raw_total
 FROM spl2.mv_all_burns m
-GROUP BY DATE_TRUNC('month', m.block_time);
+GROUP BY DATE_TRUNC('month', m.block_time), m.mint;
 
 -- -----------------------------------------------
 -- -----------------------------------------------
@@ -122,16 +146,28 @@ GROUP BY DATE_TRUNC('month', m.block_time);
 -- -----------------------------------------------
 CREATE MATERIALIZED VIEW mv_supply
     ENGINE = ReplacingMergeTree()
-    ORDER BY (stat)
+    ORDER BY (mint)
 AS
-select 'total_supply' as stat,
-       ((select sum(total) from spl2.mv_mint_per_month) -
-        (select sum(total) from spl2.mv_burn_per_month)) as total_supply;
+select m.mint                                   as mint,
+       m.total - COALESCE(b.total, 0)           as total_supply,
+       m.raw_total - COALESCE(b.raw_total, 0)   as raw_total_supply
+from (select mint, sum(total) as total, sum(raw_total) as raw_total
+      from spl2.mv_mint_per_month
+      group by mint) m
+         left join (select mint, sum(total) as total, sum(raw_total) as raw_total
+                    from spl2.mv_burn_per_month
+                    group by mint) b on b.mint = m.mint;
 
 insert into mv_supply
-select 'total_supply' as stat,
-       ((select sum(total) from spl2.mv_mint_per_month) -
-        (select sum(total) from spl2.mv_burn_per_month)) as total_supply;
+select m.mint                                   as mint,
+       m.total - COALESCE(b.total, 0)           as total_supply,
+       m.raw_total - COALESCE(b.raw_total, 0)   as raw_total_supply
+from (select mint, sum(total) as total, sum(raw_total) as raw_total
+      from spl2.mv_mint_per_month
+      group by mint) m
+         left join (select mint, sum(total) as total, sum(raw_total) as raw_total
+                    from spl2.mv_burn_per_month
+                    group by mint) b on b.mint = m.mint;
 
 
 SET use_query_cache = 0;